@@ -0,0 +1,280 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::io::{Read, Write};
+use std::process::exit;
+
+use json_flat_parser::{FlatJsonValue, JSONParser, ParseOptions, ParseResult, PointerKey, ValueType};
+use json_flat_parser::csv::{write_csv, CsvOptions, NestedCells};
+use json_flat_parser::serializer::{serialize_subtree_with_options, serialize_to_json_with_options, SerializeOptions};
+
+const USAGE: &str = "Usage: json-flat <command> [options] [file]
+
+Read JSON from file, or from stdin when file is omitted.
+
+Commands:
+  flatten              print one `pointer<TAB>type<TAB>value` line per entry
+  get <pointer>        print value at pointer
+  unflatten            read lines printed by flatten and print JSON document
+  to-csv               print elements of root array, or of array at --pointer, as CSV
+
+Options:
+  --max-depth <n>      parse up to depth n (default 10)
+  --start-at <pointer> only parse value at pointer
+  --no-arrays          don't parse arrays, keep them as raw JSON
+  --jsonl              input is JSON lines, each line is a root array element
+  --pointer <pointer>  to-csv: array to write
+  --tsv                to-csv: write TSV
+  --nested <mode>      to-csv: nested values as raw (default), drop or expand";
+
+struct Args {
+    command: String,
+    positionals: Vec<String>,
+    options: ParseOptions,
+    jsonl: bool,
+    pointer: String,
+    tsv: bool,
+    nested_cells: NestedCells,
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1).collect()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            exit(2);
+        }
+    };
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    if let Err(e) = run(args, &mut out).and_then(|_| out.flush().map_err(|e| e.to_string())) {
+        eprintln!("json-flat: {}", e);
+        exit(1);
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Args, String> {
+    let mut args = args.into_iter();
+    let command = args.next().ok_or("Missing command")?;
+    let mut parsed = Args { command, positionals: vec![], options: ParseOptions::default(), jsonl: false, pointer: String::new(), tsv: false, nested_cells: NestedCells::Raw };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-depth" => {
+                let depth = args.next().ok_or("Missing value for --max-depth")?;
+                parsed.options = parsed.options.max_depth(depth.parse::<u8>().map_err(|_| format!("Invalid depth {}", depth))?);
+            }
+            "--start-at" => parsed.options = parsed.options.start_parse_at(args.next().ok_or("Missing value for --start-at")?),
+            "--no-arrays" => parsed.options = parsed.options.parse_array(false),
+            "--jsonl" => parsed.jsonl = true,
+            "--pointer" => parsed.pointer = args.next().ok_or("Missing value for --pointer")?,
+            "--tsv" => parsed.tsv = true,
+            "--nested" => {
+                parsed.nested_cells = match args.next().as_deref() {
+                    Some("raw") => NestedCells::Raw,
+                    Some("drop") => NestedCells::Dropped,
+                    Some("expand") => NestedCells::Expanded,
+                    mode => return Err(format!("Invalid value for --nested: {}", mode.unwrap_or(""))),
+                }
+            }
+            "-h" | "--help" => parsed.command = "help".to_string(),
+            option if option.starts_with("--") => return Err(format!("Unknown option {}", option)),
+            _ => parsed.positionals.push(arg),
+        }
+    }
+    Ok(parsed)
+}
+
+fn read_input(file: Option<&String>) -> Result<String, String> {
+    match file {
+        Some(file) => fs::read_to_string(file).map_err(|e| format!("Can't read {}: {}", file, e)),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).map_err(|e| format!("Can't read stdin: {}", e))?;
+            Ok(input)
+        }
+    }
+}
+
+fn parse(input: &str, args: &Args) -> Result<ParseResult<String>, String> {
+    if args.jsonl {
+        JSONParser::parse_jsonl(input.as_bytes(), args.options.clone())
+    } else {
+        JSONParser::parse(input, args.options.clone()).map(|result| result.to_owned())
+    }
+}
+
+fn run(args: Args, out: &mut impl Write) -> Result<(), String> {
+    match args.command.as_str() {
+        "flatten" => {
+            let input = read_input(args.positionals.first())?;
+            let result = parse(&input, &args)?;
+            flatten(&result, out).map_err(|e| e.to_string())
+        }
+        "get" => {
+            let pointer = args.positionals.first().ok_or("Missing pointer")?;
+            let input = read_input(args.positionals.get(1))?;
+            let result = parse(&input, &args)?;
+            let json = get(result, pointer)?;
+            writeln!(out, "{}", json).map_err(|e| e.to_string())
+        }
+        "unflatten" => {
+            let input = read_input(args.positionals.first())?;
+            let mut entries = unflatten(&input)?;
+            writeln!(out, "{}", serialize_to_json_with_options(&mut entries, &SerializeOptions::default().raw_numbers(true))?.to_json()).map_err(|e| e.to_string())
+        }
+        "to-csv" => {
+            let input = read_input(args.positionals.first())?;
+            let result = parse(&input, &args)?;
+            let options = if args.tsv { CsvOptions::tsv() } else { CsvOptions::default() }.nested_cells(args.nested_cells);
+            write_csv(&result, &args.pointer, &options, out).map_err(|e| e.to_string())
+        }
+        "help" | "-h" | "--help" => writeln!(out, "{}", USAGE).map_err(|e| e.to_string()),
+        command => Err(format!("Unknown command {}", command)),
+    }
+}
+
+fn type_name(value_type: ValueType) -> String {
+    match value_type {
+        ValueType::Array(len) => format!("array:{}", len),
+        ValueType::Object(true, len) => format!("object:{}", len),
+        ValueType::Object(false, len) => format!("raw-object:{}", len),
+        ValueType::Number => "number".to_string(),
+        ValueType::String => "string".to_string(),
+        ValueType::Bool => "bool".to_string(),
+        ValueType::Null => "null".to_string(),
+        ValueType::None => "none".to_string(),
+    }
+}
+
+fn parse_type_name(name: &str) -> Option<ValueType> {
+    let (name, len) = match name.split_once(':') {
+        Some((name, len)) => (name, Some(len.parse::<usize>().ok()?)),
+        None => (name, None),
+    };
+    match (name, len) {
+        ("array", Some(len)) => Some(ValueType::Array(len)),
+        ("object", Some(len)) => Some(ValueType::Object(true, len)),
+        ("raw-object", Some(len)) => Some(ValueType::Object(false, len)),
+        ("number", None) => Some(ValueType::Number),
+        ("string", None) => Some(ValueType::String),
+        ("bool", None) => Some(ValueType::Bool),
+        ("null", None) => Some(ValueType::Null),
+        ("none", None) => Some(ValueType::None),
+        _ => None,
+    }
+}
+
+fn flatten(result: &ParseResult<String>, out: &mut impl Write) -> io::Result<()> {
+    for entry in result.json.iter() {
+        // Raw data of parsed objects is redundant with their children
+        let value = match entry.pointer.value_type {
+            ValueType::Object(true, _) => "",
+            _ => entry.value.as_deref().unwrap_or(""),
+        };
+        // Line breaks and tabs can only be whitespace between tokens of raw JSON, strings are kept escaped
+        let value = value.replace(['\n', '\r', '\t'], " ");
+        writeln!(out, "{}\t{}\t{}", entry.pointer.pointer, type_name(entry.pointer.value_type), value)?;
+    }
+    Ok(())
+}
+
+fn unflatten(input: &str) -> Result<Vec<FlatJsonValue<String>>, String> {
+    let mut entries = Vec::with_capacity(input.len() / 16);
+    for (i, line) in input.lines().enumerate().filter(|(_, line)| !line.is_empty()) {
+        let mut columns = line.splitn(3, '\t');
+        let (Some(pointer), Some(value_type)) = (columns.next(), columns.next()) else {
+            return Err(format!("Line {}: expected pointer<TAB>type<TAB>value", i + 1));
+        };
+        let value_type = parse_type_name(value_type).ok_or_else(|| format!("Line {}: invalid type {}", i + 1, value_type))?;
+        let value = columns.next().filter(|v| !v.is_empty() || value_type == ValueType::String).map(|v| v.to_string());
+        let value = match value_type {
+            ValueType::Object(true, _) | ValueType::Null | ValueType::None => None,
+            ValueType::Array(_) if value.as_deref() == Some("") => None,
+            _ => value,
+        };
+        let depth = pointer.matches('/').count().max(1) as u8;
+        entries.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer.to_string(), value_type, depth, entries.len()), value });
+    }
+    Ok(entries)
+}
+
+/// JSON text of value at `pointer`
+fn get(result: ParseResult<String>, pointer: &str) -> Result<String, String> {
+    serialize_subtree_with_options(&result, pointer, &SerializeOptions::default().raw_numbers(true)).map(|value| value.to_json())
+}
+
+#[cfg(test)]
+mod tests {
+    use json_flat_parser::{JSONParser, ParseOptions};
+    use json_flat_parser::serializer::serialize_to_json;
+
+    use crate::{flatten, get, parse_args, run, unflatten};
+
+    const JSON: &str = r#"{"id": 1, "name": "a", "user": {"roles": ["admin", "dev"], "meta": {"age": 3}}, "rows": [{"a": 1}, {"a": 2, "b": true}]}"#;
+
+    #[test]
+    fn flatten_unflatten_round_trip() {
+        for options in [ParseOptions::default(), ParseOptions::default().max_depth(1), ParseOptions::default().parse_array(false)] {
+            let result = JSONParser::parse(JSON, options).unwrap().to_owned();
+            let mut out: Vec<u8> = Vec::new();
+            flatten(&result, &mut out).unwrap();
+            let lines = String::from_utf8(out).unwrap();
+            let mut entries = unflatten(&lines).unwrap();
+            let mut expected = result.json.clone();
+            assert_eq!(serialize_to_json(&mut entries).unwrap().to_json(), serialize_to_json(&mut expected).unwrap().to_json());
+        }
+        let result = JSONParser::parse(JSON, ParseOptions::default()).unwrap().to_owned();
+        let mut out: Vec<u8> = Vec::new();
+        flatten(&result, &mut out).unwrap();
+        let lines = String::from_utf8(out).unwrap();
+        assert!(lines.starts_with("/id\tnumber\t1\n/name\tstring\ta\n/user\tobject:2\t\n/user/roles\tarray:2\t\n/user/roles/0\tstring\tadmin\n"));
+        assert!(unflatten("/a\tunknown\t1").is_err());
+    }
+
+    #[test]
+    fn get_value() {
+        let result = JSONParser::parse(JSON, ParseOptions::default()).unwrap().to_owned();
+        assert_eq!(get(result.clone(), "/name"), Ok("\"a\"".to_string()));
+        assert_eq!(get(result.clone(), "/user/meta"), Ok("{\n  \"age\": 3\n}".to_string()));
+        assert_eq!(get(result.clone(), "/user/roles"), Ok("[\"admin\", \"dev\"]".to_string()));
+        assert_eq!(get(result.clone(), "/rows/1/b"), Ok("true".to_string()));
+        assert_eq!(get(result, "/missing"), Err("No entry found at /missing".to_string()));
+    }
+
+    // File named after the process, so concurrent runs don't share it
+    fn temp_file(name: &str, content: &str) -> std::path::PathBuf {
+        let file = std::env::temp_dir().canonicalize().unwrap().join(format!("json-flat-{}-{}", std::process::id(), name));
+        std::fs::write(&file, content).unwrap();
+        file
+    }
+
+    #[test]
+    fn help() {
+        for args in [vec!["--help"], vec!["-h"], vec!["flatten", "--help"]] {
+            let args = parse_args(args.into_iter().map(|a| a.to_string()).collect()).unwrap();
+            let mut out: Vec<u8> = Vec::new();
+            run(args, &mut out).unwrap();
+            assert!(String::from_utf8(out).unwrap().starts_with("Usage: json-flat"));
+        }
+    }
+
+    #[test]
+    fn to_csv_command() {
+        let file = temp_file("to-csv.json", JSON);
+        let args = parse_args(vec!["to-csv".to_string(), "--pointer".to_string(), "/rows".to_string(), file.to_string_lossy().to_string()]).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        run(args, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a,b\r\n1,\r\n2,true\r\n");
+
+        // Absolute path of a root array file is not taken as a pointer
+        let root_file = temp_file("to-csv-root.json", r#"[{"a": 1}, {"a": 2}]"#);
+        assert!(root_file.is_absolute());
+        let args = parse_args(vec!["to-csv".to_string(), root_file.to_string_lossy().to_string()]).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        run(args, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a\r\n1\r\n2\r\n");
+        std::fs::remove_file(file).unwrap();
+        std::fs::remove_file(root_file).unwrap();
+        assert!(parse_args(vec!["flatten".to_string(), "--max-depth".to_string(), "x".to_string()]).is_err());
+    }
+}
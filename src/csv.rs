@@ -0,0 +1,465 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io;
+use std::io::Write;
+
+use crate::{concat_string, FlatJsonValue, GetBytes, JSONParser, ParseOptions, ParseResult, PointerKey, ValueType};
+use crate::strings::{escape, unescape};
+use crate::table::TableView;
+
+/// How cells holding a nested object or array which has not been parsed (raw JSON data) are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestedCells {
+    Raw,      // write raw JSON text
+    Dropped,  // write an empty cell
+    Expanded, // parse raw JSON and write one column per nested value, e.g: user.name
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub header: bool,
+    pub nested_cells: NestedCells,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            header: true,
+            nested_cells: NestedCells::Raw,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn tsv() -> Self {
+        Self::default().delimiter(b'\t')
+    }
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+    pub fn nested_cells(mut self, nested_cells: NestedCells) -> Self {
+        self.nested_cells = nested_cells;
+        self
+    }
+}
+
+// Cells of a row by column id and expanded nested column name ("" for the column itself)
+type Record<'a> = HashMap<(usize, Cow<'a, str>), Cow<'a, str>>;
+
+/// Write elements of array at `array_pointer` as CSV (RFC 4180), one record per element, columns are the union of elements keys.
+/// Use "" as `array_pointer` to write a root array or rows of a `parse_jsonl` result.
+pub fn write_csv<V: Debug + Clone + AsRef<str> + GetBytes>(result: &ParseResult<V>, array_pointer: &str, options: &CsvOptions, mut writer: impl Write) -> io::Result<()> {
+    let table = TableView::from(result, array_pointer).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    // Each table column is written as itself and/or as the columns expanded from its nested cells
+    let mut expanded_columns: Vec<(bool, Vec<String>)> = vec![(false, vec![]); table.columns().len()];
+    let mut records: Vec<Record> = Vec::with_capacity(table.rows_count());
+    for row in 0..table.rows_count() {
+        let mut record = HashMap::new();
+        for column in table.columns() {
+            let Some(entry) = table.cell_value(result, row, column.id) else {
+                continue;
+            };
+            if !is_nested(entry) {
+                expanded_columns[column.id].0 = true;
+                record.insert((column.id, Cow::Borrowed("")), cell_value(entry));
+                continue;
+            }
+            match options.nested_cells {
+                NestedCells::Raw => {
+                    expanded_columns[column.id].0 = true;
+                    record.insert((column.id, Cow::Borrowed("")), Cow::Borrowed(entry.value.as_ref().unwrap().as_ref()));
+                }
+                NestedCells::Dropped => expanded_columns[column.id].0 = true,
+                NestedCells::Expanded => {
+                    let raw = entry.value.as_ref().unwrap().as_ref();
+                    let nested = JSONParser::parse(raw, ParseOptions::default().max_depth(u8::MAX).dialect(result.dialect))
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Can't expand cell {}: {}", entry.pointer.pointer, e)))?;
+                    for (i, nested_entry) in nested.json.iter().enumerate() {
+                        let is_parent = nested.json.get(i + 1)
+                            .is_some_and(|next| next.pointer.pointer.len() > nested_entry.pointer.pointer.len()
+                                && next.pointer.pointer.starts_with(nested_entry.pointer.pointer.as_str())
+                                && next.pointer.pointer.as_bytes()[nested_entry.pointer.pointer.len()] == b'/');
+                        if is_parent {
+                            continue;
+                        }
+                        let name = nested_entry.pointer.pointer.replace('/', ".");
+                        let names = &mut expanded_columns[column.id].1;
+                        if !names.contains(&name) {
+                            names.push(name.clone());
+                        }
+                        let value = match nested_entry.value {
+                            Some(value) if nested_entry.pointer.value_type == ValueType::String => unescape(value),
+                            Some(value) => Cow::Borrowed(value),
+                            None => Cow::Borrowed(""),
+                        };
+                        record.insert((column.id, Cow::Owned(name)), Cow::Owned(value.into_owned()));
+                    }
+                }
+            }
+        }
+        records.push(record);
+    }
+
+    let mut header: Vec<(usize, String, String)> = vec![];
+    for column in table.columns() {
+        let (itself, nested) = &expanded_columns[column.id];
+        if *itself {
+            header.push((column.id, String::new(), column.name.clone()));
+        }
+        for name in nested {
+            header.push((column.id, name.clone(), format!("{}{}", column.name, name)));
+        }
+    }
+
+    if options.header {
+        write_record(&mut writer, header.iter().map(|(_, _, name)| name.as_str()), options.delimiter)?;
+    }
+    for record in records.iter() {
+        write_record(&mut writer, header.iter().map(|(column_id, name, _)| {
+            record.get(&(*column_id, Cow::Borrowed(name.as_str()))).map_or("", |v| v.as_ref())
+        }), options.delimiter)?;
+    }
+    writer.flush()
+}
+
+fn is_nested<V: Debug + Clone + AsRef<str> + GetBytes>(entry: &FlatJsonValue<V>) -> bool {
+    matches!(entry.pointer.value_type, ValueType::Object(..) | ValueType::Array(_)) && entry.value.is_some()
+}
+
+fn cell_value<V: Debug + Clone + AsRef<str> + GetBytes>(entry: &FlatJsonValue<V>) -> Cow<'_, str> {
+    match entry.value.as_ref() {
+        Some(value) if entry.pointer.value_type == ValueType::String => unescape(value.as_ref()),
+        Some(value) => Cow::Borrowed(value.as_ref()),
+        None => Cow::Borrowed(""),
+    }
+}
+
+fn write_record<'a>(writer: &mut impl Write, fields: impl Iterator<Item=&'a str>, delimiter: u8) -> io::Result<()> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            writer.write_all(&[delimiter])?;
+        }
+        if field.bytes().any(|b| b == delimiter || b == b'"' || b == b'\r' || b == b'\n') {
+            writer.write_all(b"\"")?;
+            writer.write_all(field.replace('"', "\"\"").as_bytes())?;
+            writer.write_all(b"\"")?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\r\n")
+}
+
+impl JSONParser {
+    /// Read CSV/TSV with a header row into the same layout as `parse_jsonl` output: one `/N` object per record, one `/N/column` entry per cell.
+    /// Headers like `a/b` or `a.b` are nested objects, cell types are inferred: empty cell or "null" is Null, "true"/"false" is Bool, JSON numbers are Number.
+    pub fn parse_csv(input: &[u8], delimiter: u8) -> Result<ParseResult<String>, String> {
+        let input = std::str::from_utf8(input).map_err(|e| format!("Invalid UTF-8: {}", e))?;
+        let mut records = CsvRecords::new(input, delimiter);
+        let Some(header) = records.next() else {
+            return Err("Missing header row".to_string());
+        };
+        let (_, header) = header?;
+        let (columns_count, layout) = csv_layout(&header)?;
+        let max_depth = layout.iter().map(|(_, depth, _)| *depth as usize + 1).max().unwrap_or(1);
+
+        let mut all_values: Vec<FlatJsonValue<String>> = Vec::with_capacity(1024);
+        all_values.push(FlatJsonValue {
+            pointer: PointerKey::from_pointer(String::new(), ValueType::Array(0), 1, 0),
+            value: None,
+        });
+        let mut row_index = 0_usize;
+        for record in records {
+            let (line_number, cells) = record?;
+            if cells.len() > header.len() {
+                return Err(format!("Error parsing CSV at line {}: expected {} fields, found {}", line_number, header.len(), cells.len()));
+            }
+            let row_pointer = concat_string!("/", row_index.to_string());
+            all_values.push(FlatJsonValue {
+                pointer: PointerKey::from_pointer(row_pointer.clone(), ValueType::Object(true, columns_count), 1, 0),
+                value: None,
+            });
+            for (relative_pointer, depth, node) in layout.iter() {
+                let (value_type, value) = match node {
+                    CsvNode::Object(count) => (ValueType::Object(true, *count), None),
+                    CsvNode::Cell(i) => infer_cell(cells.get(*i).map_or("", |c| c.as_str())),
+                };
+                all_values.push(FlatJsonValue {
+                    pointer: PointerKey::from_pointer(concat_string!(row_pointer, relative_pointer), value_type, depth + 1, 0),
+                    value,
+                });
+            }
+            row_index += 1;
+        }
+
+        let options = ParseOptions::default().max_depth(u8::try_from(max_depth).unwrap_or(u8::MAX));
+        Ok(Self::jsonl_result(all_values, row_index, max_depth, &options))
+    }
+}
+
+// Relative pointer, depth relative to row, node
+type CsvLayout = Vec<(String, u8, CsvNode)>;
+
+enum CsvNode {
+    Object(usize), // number of keys
+    Cell(usize),   // index of the field in records
+}
+
+#[derive(Default)]
+struct CsvHeaderTree {
+    key: String,
+    field: Option<usize>,
+    children: Vec<CsvHeaderTree>,
+}
+
+/// Build entries layout of a row from header, in document order. Returns number of top level keys too.
+fn csv_layout(header: &[String]) -> Result<(usize, CsvLayout), String> {
+    let mut root = CsvHeaderTree::default();
+    for (i, name) in header.iter().enumerate() {
+        let name = name.strip_prefix('/').unwrap_or(name);
+        let mut node = &mut root;
+        for key in name.split(['/', '.']) {
+            if node.field.is_some() {
+                return Err(format!("Column {} is nested in a column which is not an object", header[i]));
+            }
+            let child = match node.children.iter().position(|c| c.key.eq(key)) {
+                Some(child) => child,
+                None => {
+                    node.children.push(CsvHeaderTree { key: key.to_string(), ..Default::default() });
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[child];
+        }
+        if node.field.is_some() || !node.children.is_empty() {
+            return Err(format!("Column {} is defined more than once", header[i]));
+        }
+        node.field = Some(i);
+    }
+
+    fn walk(node: &CsvHeaderTree, pointer: &str, depth: u8, layout: &mut CsvLayout) {
+        for child in node.children.iter() {
+            let child_pointer = concat_string!(pointer, "/", child.key);
+            match child.field {
+                Some(field) => layout.push((child_pointer, depth, CsvNode::Cell(field))),
+                None => {
+                    layout.push((child_pointer.clone(), depth, CsvNode::Object(child.children.len())));
+                    walk(child, &child_pointer, depth + 1, layout);
+                }
+            }
+        }
+    }
+    let mut layout = Vec::with_capacity(header.len());
+    walk(&root, "", 1, &mut layout);
+    Ok((root.children.len(), layout))
+}
+
+fn infer_cell(cell: &str) -> (ValueType, Option<String>) {
+    match cell {
+        "" | "null" => (ValueType::Null, None),
+        "true" | "false" => (ValueType::Bool, Some(cell.to_string())),
+        _ if is_json_number(cell) => (ValueType::Number, Some(cell.to_string())),
+        _ => (ValueType::String, Some(escape(cell).into_owned())),
+    }
+}
+
+pub(crate) fn is_json_number(s: &str) -> bool {
+    let bytes = s.strip_prefix('-').unwrap_or(s).as_bytes();
+    let int_len = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+    if int_len == 0 || (int_len > 1 && bytes[0] == b'0') {
+        return false;
+    }
+    let mut rest = &bytes[int_len..];
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let len = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+        if len == 0 {
+            return false;
+        }
+        rest = &fraction[len..];
+    }
+    if let Some(exponent) = rest.strip_prefix(b"e").or_else(|| rest.strip_prefix(b"E")) {
+        let exponent = exponent.strip_prefix(b"+").or_else(|| exponent.strip_prefix(b"-")).unwrap_or(exponent);
+        return !exponent.is_empty() && exponent.iter().all(|b| b.is_ascii_digit());
+    }
+    rest.is_empty()
+}
+
+/// RFC 4180 records reader, yields line number where the record starts and its fields. Blank lines are skipped.
+struct CsvRecords<'a> {
+    input: &'a str,
+    index: usize,
+    line: usize,
+    delimiter: u8,
+}
+
+impl<'a> CsvRecords<'a> {
+    fn new(input: &'a str, delimiter: u8) -> Self {
+        let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+        Self { input, index: 0, line: 1, delimiter }
+    }
+
+    fn read_record(&mut self) -> Result<Vec<String>, String> {
+        let bytes = self.input.as_bytes();
+        let record_line = self.line;
+        let mut fields = vec![];
+        loop {
+            let mut field = String::new();
+            if bytes.get(self.index) == Some(&b'"') {
+                self.index += 1;
+                loop {
+                    let Some(quote) = self.input[self.index..].find('"') else {
+                        return Err(format!("Error parsing CSV at line {}: unterminated quoted field", record_line));
+                    };
+                    let chunk = &self.input[self.index..self.index + quote];
+                    self.line += chunk.matches('\n').count();
+                    field.push_str(chunk);
+                    self.index += quote + 1;
+                    if bytes.get(self.index) == Some(&b'"') {
+                        field.push('"');
+                        self.index += 1;
+                    } else {
+                        break;
+                    }
+                }
+            } else {
+                let start = self.index;
+                while self.index < bytes.len() && bytes[self.index] != self.delimiter && bytes[self.index] != b'\r' && bytes[self.index] != b'\n' {
+                    self.index += 1;
+                }
+                field.push_str(&self.input[start..self.index]);
+            }
+            fields.push(field);
+
+            match bytes.get(self.index) {
+                Some(b) if *b == self.delimiter => self.index += 1,
+                Some(b'\r') if bytes.get(self.index + 1) == Some(&b'\n') => {
+                    self.index += 2;
+                    self.line += 1;
+                    return Ok(fields);
+                }
+                Some(b'\n') | Some(b'\r') => {
+                    self.index += 1;
+                    self.line += 1;
+                    return Ok(fields);
+                }
+                None => return Ok(fields),
+                Some(_) => return Err(format!("Error parsing CSV at line {}: unexpected content after quoted field", self.line)),
+            }
+        }
+    }
+}
+
+impl Iterator for CsvRecords<'_> {
+    type Item = Result<(usize, Vec<String>), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.input.len() {
+            let line = self.line;
+            match self.read_record() {
+                Ok(fields) if fields.len() == 1 && fields[0].is_empty() => continue,
+                Ok(fields) => return Some(Ok((line, fields))),
+                Err(e) => {
+                    self.index = self.input.len();
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dialect, JSONParser, ParseOptions};
+    use crate::csv::{write_csv, CsvOptions, NestedCells};
+    use crate::ValueType;
+
+    fn csv(json: &str, options: ParseOptions, csv_options: CsvOptions) -> String {
+        let result = JSONParser::parse(json, options).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_csv(&result, "", &csv_options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn write_csv_quoting() {
+        let json = r#"[{"id": 1, "name": "a,b", "quote": "say \"hi\"", "text": "line\nbreak"}, {"id": 2, "flag": true, "none": null}]"#;
+        assert_eq!(csv(json, ParseOptions::default(), CsvOptions::default()),
+                   "id,name,quote,text,flag,none\r\n1,\"a,b\",\"say \"\"hi\"\"\",\"line\nbreak\",,\r\n2,,,,true,\r\n");
+        assert_eq!(csv(json, ParseOptions::default(), CsvOptions::tsv().header(false)),
+                   "1\ta,b\t\"say \"\"hi\"\"\"\t\"line\nbreak\"\t\t\r\n2\t\t\t\ttrue\t\r\n");
+    }
+
+    #[test]
+    fn write_csv_nested_cells() {
+        let json = r#"[{"id": 1, "user": {"name": "a", "roles": ["x"]}}, {"id": 2, "user": "anonymous"}]"#;
+        let options = ParseOptions::default().max_depth(2);
+        assert_eq!(csv(json, options.clone(), CsvOptions::default()),
+                   "id,user\r\n1,\"{\"\"name\"\": \"\"a\"\", \"\"roles\"\": [\"\"x\"\"]}\"\r\n2,anonymous\r\n");
+        assert_eq!(csv(json, options.clone(), CsvOptions::default().nested_cells(NestedCells::Dropped)),
+                   "id,user\r\n1,\r\n2,anonymous\r\n");
+        assert_eq!(csv(json, options.clone(), CsvOptions::default().nested_cells(NestedCells::Expanded)),
+                   "id,user,user.name,user.roles.0\r\n1,,a,x\r\n2,anonymous,,\r\n");
+        // Same columns as when document is fully parsed, except for scalar value of "user"
+        assert_eq!(csv(json, ParseOptions::default(), CsvOptions::default()),
+                   "id,user.name,user.roles.0,user\r\n1,a,x,\r\n2,,,anonymous\r\n");
+
+        let json5 = "[{id: 1, user: {name: 'a', /* comment */ roles: ['x',],},}]";
+        assert_eq!(csv(json5, options.dialect(Dialect::Json5), CsvOptions::default().nested_cells(NestedCells::Expanded)),
+                   "id,user.name,user.roles.0\r\n1,a,x\r\n");
+    }
+
+    #[test]
+    fn write_csv_jsonl() {
+        let result = JSONParser::parse_jsonl(b"{\"a\": 1}\n{\"b\": \"\\u00e9\\ud83d\\ude00\"}\n", ParseOptions::default()).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_csv(&result, "", &CsvOptions::default(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a,b\r\n1,\r\n,é😀\r\n");
+    }
+
+    #[test]
+    fn parse_csv() {
+        let content = "id,user.name,user/age,note,active\r\n1,a,10,\"hello, \"\"world\"\"\",true\r\n\r\n2,b,,\"multi\nline\",null\n3,c,1e3,-0.5\n";
+        let result = JSONParser::parse_csv(content.as_bytes(), b',').unwrap();
+        let entries = result.json.iter().map(|e| (e.pointer.pointer.as_str(), e.pointer.value_type, e.pointer.depth, e.value.as_deref())).collect::<Vec<_>>();
+        assert_eq!(&entries[0..8], &[
+            ("", ValueType::Array(3), 1, None),
+            ("/0", ValueType::Object(true, 4), 2, None),
+            ("/0/id", ValueType::Number, 2, Some("1")),
+            ("/0/user", ValueType::Object(true, 2), 2, None),
+            ("/0/user/name", ValueType::String, 3, Some("a")),
+            ("/0/user/age", ValueType::Number, 3, Some("10")),
+            ("/0/note", ValueType::String, 2, Some("hello, \\\"world\\\"")),
+            ("/0/active", ValueType::Bool, 2, Some("true")),
+        ]);
+        assert_eq!(entries[12], ("/1/user/age", ValueType::Null, 3, None));
+        assert_eq!(entries[13], ("/1/note", ValueType::String, 2, Some("multi\\nline")));
+        assert_eq!(entries[19], ("/2/user/age", ValueType::Number, 3, Some("1e3")));
+        assert_eq!(entries[20], ("/2/note", ValueType::Number, 2, Some("-0.5")));
+        assert_eq!(entries[21], ("/2/active", ValueType::Null, 2, None));
+        assert_eq!(result.json.iter().filter(|e| e.pointer.pointer.matches('/').count() == 1).map(|e| e.pointer.position).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(result.max_json_depth, 3);
+
+        let mut out: Vec<u8> = Vec::new();
+        write_csv(&result, "", &CsvOptions::default(), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "id,user.name,user.age,note,active\r\n1,a,10,\"hello, \"\"world\"\"\",true\r\n2,b,,\"multi\nline\",\r\n3,c,1e3,-0.5,\r\n");
+    }
+
+    #[test]
+    fn parse_csv_errors() {
+        assert!(JSONParser::parse_csv(b"", b',').is_err());
+        assert_eq!(JSONParser::parse_csv(b"a,a.b\n1,2", b',').err().unwrap(), "Column a.b is nested in a column which is not an object");
+        assert_eq!(JSONParser::parse_csv(b"a,b\n1,2,3", b',').err().unwrap(), "Error parsing CSV at line 2: expected 2 fields, found 3");
+        assert_eq!(JSONParser::parse_csv(b"a\tb\n1\t\"2", b'\t').err().unwrap(), "Error parsing CSV at line 2: unterminated quoted field");
+        assert_eq!(JSONParser::parse_csv(b"a\tb\n\"1\"x\t2", b'\t').err().unwrap(), "Error parsing CSV at line 2: unexpected content after quoted field");
+    }
+}
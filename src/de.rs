@@ -0,0 +1,401 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::{Debug, Display};
+
+use serde::de;
+use serde::de::{DeserializeSeed, IntoDeserializer, Visitor};
+use serde::de::value::BorrowedStrDeserializer;
+use serde::forward_to_deserialize_any;
+
+use crate::{concat_string, has_children, Dialect, FlatJsonValue, GetBytes, ParseResult, ValueType};
+use crate::serializer::raw_to_json;
+use crate::strings::{json5_number_to_json, unescape};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeserializeError(pub String);
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        DeserializeError(msg.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Entry(usize),
+    RootObject, // root object has no entry, its members are all depth 1 entries
+    Missing(String),
+}
+
+/// Deserializer of the value at a pointer of a `ParseResult`, see `ParseResult::at`.
+/// Objects and arrays which have not been parsed are deserialized from their raw data with serde_json,
+/// raw data of Jsonc and Json5 documents being written as JSON first.
+pub struct Subtree<'de, V: Debug + Clone + AsRef<str> + GetBytes> {
+    entries: &'de [FlatJsonValue<V>],
+    node: Node,
+    dialect: Dialect,
+}
+
+impl<V: Debug + Clone + AsRef<str> + GetBytes> ParseResult<V> {
+    /// Deserializer of the value at `pointer`, e.g: `Info::deserialize(result.at("/info"))`. Use "" for the whole document.
+    pub fn at(&self, pointer: &str) -> Subtree<'_, V> {
+        let node = match self.json.iter().position(|e| e.pointer.pointer.eq(pointer)) {
+            Some(index) => Node::Entry(index),
+            None if pointer.is_empty() => Node::RootObject,
+            None => Node::Missing(pointer.to_string()),
+        };
+        Subtree { entries: &self.json, node, dialect: self.dialect }
+    }
+}
+
+impl<'de, V: Debug + Clone + AsRef<str> + GetBytes> Subtree<'de, V> {
+    fn entry(&self) -> Option<&'de FlatJsonValue<V>> {
+        match self.node {
+            Node::Entry(index) => Some(&self.entries[index]),
+            _ => None,
+        }
+    }
+
+    /// Raw data of an object or array which has not been parsed.
+    /// Parsed arrays may keep their raw data (expanded nodes, JSONL array rows), their children are the source then.
+    fn raw(&self) -> Option<&'de str> {
+        let Node::Entry(index) = self.node else {
+            return None;
+        };
+        let entry = &self.entries[index];
+        match entry.pointer.value_type {
+            ValueType::Object(false, _) | ValueType::Array(_) if !has_children(self.entries, index) => entry.value.as_ref().map(|v| v.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Direct children entries, relying on entries being in document order
+    fn children(&self) -> (usize, Vec<usize>) {
+        let (prefix, start) = match self.node {
+            Node::Entry(index) => (concat_string!(self.entries[index].pointer.pointer, "/"), index + 1),
+            _ => ("/".to_string(), 0),
+        };
+        let mut children = vec![];
+        let mut i = start;
+        while i < self.entries.len() && self.entries[i].pointer.pointer.starts_with(prefix.as_str()) {
+            if !self.entries[i].pointer.pointer[prefix.len()..].contains('/') {
+                children.push(i);
+            }
+            i += 1;
+        }
+        (prefix.len(), children)
+    }
+
+    fn child(&self, index: usize) -> Self {
+        Subtree { entries: self.entries, node: Node::Entry(index), dialect: self.dialect }
+    }
+
+    /// Raw data as JSON text, borrowed for Json documents
+    fn raw_json(&self) -> Result<Option<Cow<'de, str>>, DeserializeError> {
+        match self.raw() {
+            Some(raw) if self.dialect == Dialect::Json => Ok(Some(Cow::Borrowed(raw))),
+            Some(raw) => raw_to_json(raw, self.dialect).map(|json| Some(Cow::Owned(json))).map_err(DeserializeError),
+            None => Ok(None),
+        }
+    }
+}
+
+fn json_error(e: serde_json::Error) -> DeserializeError {
+    DeserializeError(e.to_string())
+}
+
+// Deserialize raw data of `subtree` with serde_json if any, JSON text written from Jsonc or Json5 is owned so it goes through serde_json::Value
+macro_rules! deserialize_raw {
+    ($subtree:expr, $method:ident($($arg:expr),*)) => {
+        match $subtree.raw_json()? {
+            Some(Cow::Borrowed(raw)) => return serde_json::Deserializer::from_str(raw).$method($($arg),*).map_err(json_error),
+            Some(Cow::Owned(json)) => return serde_json::from_str::<serde_json::Value>(&json).map_err(json_error)?.$method($($arg),*).map_err(json_error),
+            None => {}
+        }
+    };
+}
+
+impl<'de, V: Debug + Clone + AsRef<str> + GetBytes> de::Deserializer<'de> for Subtree<'de, V> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, Self::Error> {
+        deserialize_raw!(self, deserialize_any(visitor));
+        let entry = match self.node {
+            Node::Missing(ref pointer) => return Err(DeserializeError(format!("No value at {}", pointer))),
+            Node::RootObject => {
+                let (prefix_len, children) = self.children();
+                return visitor.visit_map(Members { subtree: self, children: children.into_iter(), prefix_len, current: None });
+            }
+            Node::Entry(index) => &self.entries[index],
+        };
+        let value = entry.value.as_ref().map_or("", |v| v.as_ref());
+        match entry.pointer.value_type {
+            ValueType::Object(..) => {
+                let (prefix_len, children) = self.children();
+                visitor.visit_map(Members { subtree: self, children: children.into_iter(), prefix_len, current: None })
+            }
+            ValueType::Array(_) => {
+                let (prefix_len, children) = self.children();
+                visitor.visit_seq(Members { subtree: self, children: children.into_iter(), prefix_len, current: None })
+            }
+            ValueType::Number => {
+                let number = json5_number_to_json(value).unwrap_or(Cow::Borrowed(value));
+                let value = number.as_ref();
+                if let Ok(n) = value.parse::<u64>() {
+                    visitor.visit_u64(n)
+                } else if let Ok(n) = value.parse::<i64>() {
+                    visitor.visit_i64(n)
+                } else {
+                    value.parse::<f64>().map_err(|_| DeserializeError(format!("Invalid number {} at {}", value, entry.pointer.pointer)))
+                        .and_then(|n| visitor.visit_f64(n))
+                }
+            }
+            ValueType::String => match unescape(value) {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            ValueType::Bool => visitor.visit_bool(value == "true"),
+            ValueType::Null | ValueType::None => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_option<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, Self::Error> {
+        match self.entry().map(|e| e.pointer.value_type) {
+            Some(ValueType::Null) | Some(ValueType::None) => visitor.visit_none(),
+            _ if matches!(self.node, Node::Missing(_)) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<W: Visitor<'de>>(self, name: &'static str, visitor: W) -> Result<W::Value, Self::Error> {
+        deserialize_raw!(self, deserialize_newtype_struct(name, visitor));
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<W: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: W) -> Result<W::Value, Self::Error> {
+        deserialize_raw!(self, deserialize_enum(name, variants, visitor));
+        match self.entry().map(|e| e.pointer.value_type) {
+            Some(ValueType::String) => {
+                let variant = unescape(self.entry().unwrap().value.as_ref().map_or("", |v| v.as_ref())).into_owned();
+                visitor.visit_enum(variant.into_deserializer())
+            }
+            Some(ValueType::Object(..)) => {
+                let (prefix_len, children) = self.children();
+                if children.len() != 1 {
+                    return Err(DeserializeError(format!("Expected an object with a single key for enum {}", name)));
+                }
+                visitor.visit_enum(Variant { subtree: self, child: children[0], prefix_len })
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        <W: Visitor<'de>>
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+// Members of an object or elements of an array
+struct Members<'de, V: Debug + Clone + AsRef<str> + GetBytes> {
+    subtree: Subtree<'de, V>,
+    children: std::vec::IntoIter<usize>,
+    prefix_len: usize,
+    current: Option<usize>,
+}
+
+impl<'de, V: Debug + Clone + AsRef<str> + GetBytes> de::MapAccess<'de> for Members<'de, V> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        let Some(child) = self.children.next() else {
+            return Ok(None);
+        };
+        self.current = Some(child);
+        let key = &self.subtree.entries[child].pointer.pointer[self.prefix_len..];
+        seed.deserialize(BorrowedStrDeserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, Self::Error> {
+        let child = self.current.take().ok_or_else(|| DeserializeError("Value requested before key".to_string()))?;
+        seed.deserialize(self.subtree.child(child))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.children.len())
+    }
+}
+
+impl<'de, V: Debug + Clone + AsRef<str> + GetBytes> de::SeqAccess<'de> for Members<'de, V> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>, Self::Error> {
+        match self.children.next() {
+            Some(child) => seed.deserialize(self.subtree.child(child)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.children.len())
+    }
+}
+
+// Enum represented as an object with a single key: the variant
+struct Variant<'de, V: Debug + Clone + AsRef<str> + GetBytes> {
+    subtree: Subtree<'de, V>,
+    child: usize,
+    prefix_len: usize,
+}
+
+impl<'de, V: Debug + Clone + AsRef<str> + GetBytes> de::EnumAccess<'de> for Variant<'de, V> {
+    type Error = DeserializeError;
+    type Variant = Subtree<'de, V>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Self::Variant), Self::Error> {
+        let key = &self.subtree.entries[self.child].pointer.pointer[self.prefix_len..];
+        let variant = seed.deserialize(BorrowedStrDeserializer::<DeserializeError>::new(key))?;
+        Ok((variant, self.subtree.child(self.child)))
+    }
+}
+
+impl<'de, V: Debug + Clone + AsRef<str> + GetBytes> de::VariantAccess<'de> for Subtree<'de, V> {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, Self::Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<W: Visitor<'de>>(self, _len: usize, visitor: W) -> Result<W::Value, Self::Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<W: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: W) -> Result<W::Value, Self::Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use crate::{Dialect, JSONParser, ParseOptions};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Info<'a> {
+        title: &'a str,
+        description: String,
+        version: u32,
+        ratio: f32,
+        beta: bool,
+        license: Option<License>,
+        contact: Option<License>,
+        tags: Vec<String>,
+        kind: Kind,
+        extra: HashMap<String, i64>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct License {
+        name: String,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Kind {
+        Public,
+        Private { owner: String },
+    }
+
+    const JSON: &str = r#"{"info": {"title": "api", "description": "a \"quoted\"\nline", "version": 3, "ratio": 0.5, "beta": false,
+        "license": {"name": "MIT"}, "contact": null, "tags": ["a", "b"], "kind": {"Private": {"owner": "me"}}, "extra": {"x": -1}}, "paths": {}}"#;
+
+    fn expected() -> Info<'static> {
+        Info {
+            title: "api",
+            description: "a \"quoted\"\nline".to_string(),
+            version: 3,
+            ratio: 0.5,
+            beta: false,
+            license: Some(License { name: "MIT".to_string() }),
+            contact: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+            kind: Kind::Private { owner: "me".to_string() },
+            extra: HashMap::from([("x".to_string(), -1)]),
+        }
+    }
+
+    #[test]
+    fn deserialize_subtree() {
+        let result = JSONParser::parse(JSON, ParseOptions::default()).unwrap();
+        assert_eq!(Info::deserialize(result.at("/info")).unwrap(), expected());
+        assert_eq!(License::deserialize(result.at("/info/license")).unwrap(), License { name: "MIT".to_string() });
+        assert_eq!(Vec::<String>::deserialize(result.at("/info/tags")).unwrap(), vec!["a", "b"]);
+        assert!(License::deserialize(result.at("/missing")).is_err());
+        assert_eq!(Option::<License>::deserialize(result.at("/missing")).unwrap(), None);
+    }
+
+    #[test]
+    fn deserialize_raw_subtree() {
+        // "info" is not parsed, it is deserialized from its raw data
+        let result = JSONParser::parse(JSON, ParseOptions::default().max_depth(1)).unwrap().to_owned();
+        assert_eq!(Info::deserialize(result.at("/info")).unwrap(), expected());
+
+        let result = JSONParser::parse(JSON, ParseOptions::default().max_depth(2).parse_array(false)).unwrap();
+        assert_eq!(Info::deserialize(result.at("/info")).unwrap(), expected());
+        assert_eq!(Kind::deserialize(result.at("/info/kind")).unwrap(), Kind::Private { owner: "me".to_string() });
+
+        #[derive(Deserialize)]
+        struct Document<'a> {
+            #[serde(borrow)]
+            info: Info<'a>,
+        }
+        assert_eq!(Document::deserialize(result.at("")).unwrap().info, expected());
+    }
+
+    #[test]
+    fn deserialize_root_array() {
+        let result = JSONParser::parse_jsonl(b"{\"name\": \"a\"}\n{\"name\": \"b\"}\n", ParseOptions::default()).unwrap();
+        let rows = Vec::<License>::deserialize(result.at("")).unwrap();
+        assert_eq!(rows, vec![License { name: "a".to_string() }, License { name: "b".to_string() }]);
+        let result = JSONParser::parse(r#"["Public", {"Private": {"owner": "x"}}]"#, ParseOptions::default()).unwrap();
+        assert_eq!(Vec::<Kind>::deserialize(result.at("")).unwrap(), vec![Kind::Public, Kind::Private { owner: "x".to_string() }]);
+    }
+
+    #[test]
+    fn deserialize_jsonc_and_json5() {
+        let result = JSONParser::parse("{version: 0x10, extra: {a: 1, /* comment */ b: +2,}, tags: ['x',],}", ParseOptions::default().dialect(Dialect::Json5).max_depth(1)).unwrap();
+        assert_eq!(u32::deserialize(result.at("/version")).unwrap(), 16);
+        assert_eq!(HashMap::<String, i64>::deserialize(result.at("/extra")).unwrap(), HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]));
+        assert_eq!(Vec::<String>::deserialize(result.at("/tags")).unwrap(), vec!["x".to_string()]);
+
+        let result = JSONParser::parse("{\"license\": {\"name\": \"MIT\", // comment\n}}", ParseOptions::default().dialect(Dialect::Jsonc).max_depth(1)).unwrap();
+        assert_eq!(License::deserialize(result.at("/license")).unwrap(), License { name: "MIT".to_string() });
+    }
+
+    #[test]
+    fn deserialize_edited_array_keeping_raw_data() {
+        let mut result = JSONParser::parse(r#"{"a": [1, 2]}"#, ParseOptions::default().max_depth(1)).unwrap().to_owned();
+        JSONParser::expand_node_owned(&mut result, "/a", 1).unwrap();
+        result.json.iter_mut().find(|e| e.pointer.pointer.eq("/a/0")).unwrap().value = Some("5".to_string());
+        assert_eq!(Vec::<i32>::deserialize(result.at("/a")).unwrap(), vec![5, 2]);
+
+        let mut rows = JSONParser::parse_jsonl(b"[1, 2]\n[3]\n", ParseOptions::default()).unwrap();
+        rows.json.iter_mut().find(|e| e.pointer.pointer.eq("/0/0")).unwrap().value = Some("5".to_string());
+        assert_eq!(Vec::<Vec<i32>>::deserialize(rows.at("")).unwrap(), vec![vec![5, 2], vec![3]]);
+    }
+}
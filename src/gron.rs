@@ -0,0 +1,293 @@
+//! Line oriented pointer/value text format, one entry per line, which can be edited or grepped then turned back into JSON.
+//!
+//! ```text
+//! /name = "dashboard"
+//! /panels = []
+//! /panels/0 = {}
+//! /panels/0/id = 1
+//! /panels/0/title = "CPU \"usage\""
+//! /panels/0/visible = true
+//! /panels/0/links = null
+//! /panels/0/targets = [{"expr": "rate(cpu[5m])"}]
+//! ```
+//!
+//! Each line is `<pointer> = <value>`, the value literal is the type tag of the entry:
+//! - `"..."` a string, kept JSON escaped
+//! - a JSON number, `true`, `false` or `null`
+//! - `{}` and `[]` a parsed object or array, its members follow on their own lines
+//! - any other `{...}` or `[...]` an object or array kept unparsed, written on a single line
+//!
+//! The root array is written as ` = []` (empty pointer), the root object has no line.
+//! Pointers containing ` = ` or a new line are written as a JSON string, e.g. `"/a = b" = 1`.
+//! When parsing, missing parents are created: a parent is an array when the following segment is numeric, an object otherwise.
+//! Array elements are expected in index order, blank lines are ignored.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io;
+use std::io::Write;
+
+use crate::csv::is_json_number;
+use crate::strings::{escape, unescape};
+use crate::{has_children, FlatJsonValue, GetBytes, ParseResult, PointerKey, ValueType};
+
+/// Write each entry of the result as a `pointer = value` line.
+pub fn write_gron<V: Debug + Clone + AsRef<str> + GetBytes>(result: &ParseResult<V>, mut writer: impl Write) -> io::Result<()> {
+    let mut line: Vec<u8> = Vec::with_capacity(256);
+    for (i, entry) in result.json.iter().enumerate() {
+        line.clear();
+        let pointer = entry.pointer.pointer.as_str();
+        if pointer.contains(" = ") || pointer.ends_with(" =") || pointer.contains(['\n', '\r']) {
+            line.push(b'"');
+            line.extend_from_slice(escape(pointer).as_bytes());
+            line.push(b'"');
+        } else {
+            line.extend_from_slice(pointer.as_bytes());
+        }
+        line.extend_from_slice(b" = ");
+        let value = entry.value.as_ref().map(|v| v.as_ref());
+        match (&entry.pointer.value_type, value) {
+            (ValueType::Object(true, _), _) => line.extend_from_slice(b"{}"),
+            (ValueType::Array(_), _) if entry.value.is_none() || has_children(&result.json, i) => line.extend_from_slice(b"[]"),
+            (ValueType::Object(false, _), Some(raw)) | (ValueType::Array(_), Some(raw)) => {
+                line.extend(raw.trim().bytes().map(|b| if matches!(b, b'\n' | b'\r' | b'\t') { b' ' } else { b }));
+            }
+            (ValueType::String, Some(s)) => {
+                line.push(b'"');
+                line.extend_from_slice(s.as_bytes());
+                line.push(b'"');
+            }
+            (ValueType::Number, Some(v)) | (ValueType::Bool, Some(v)) => line.extend_from_slice(v.as_bytes()),
+            _ => line.extend_from_slice(b"null"),
+        }
+        line.push(b'\n');
+        writer.write_all(&line)?;
+    }
+    writer.flush()
+}
+
+/// Parse `pointer = value` lines back into flat entries, which can be turned into JSON with `serialize_to_json`.
+pub fn parse_gron(input: &str) -> Result<Vec<FlatJsonValue<String>>, String> {
+    let mut entries: Vec<FlatJsonValue<String>> = Vec::with_capacity(input.len() / 16);
+    let mut indexes: HashMap<String, usize> = HashMap::new();
+    let mut root_is_array: Option<bool> = None;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line_number = i + 1;
+        let Some((pointer, literal)) = split_line(line) else {
+            return Err(format!("Error parsing gron at line {}: expected `pointer = value`", line_number));
+        };
+        let pointer = pointer.as_ref();
+        let (value_type, value) = parse_literal(literal.trim())
+            .ok_or_else(|| format!("Error parsing gron at line {}: invalid value", line_number))?;
+
+        if pointer.is_empty() {
+            let is_array = match (&value_type, &value) {
+                (ValueType::Array(_), None) => true,
+                (ValueType::Object(true, _), _) => false,
+                _ => return Err(format!("Error parsing gron at line {}: root can only be `{{}}` or `[]`", line_number)),
+            };
+            match root_is_array {
+                Some(root) if root != is_array => return Err(format!("Error parsing gron at line {}: root is defined more than once", line_number)),
+                Some(_) => {}
+                None => declare_root(&mut entries, &mut indexes, &mut root_is_array, is_array),
+            }
+            continue;
+        }
+        if !pointer.starts_with('/') {
+            return Err(format!("Error parsing gron at line {}: pointer {} should start with /", line_number, pointer));
+        }
+
+        let segments: Vec<&str> = pointer[1..].split('/').collect();
+        if root_is_array.is_none() {
+            declare_root(&mut entries, &mut indexes, &mut root_is_array, is_index(segments[0]));
+        }
+        if root_is_array != Some(is_index(segments[0])) {
+            return Err(format!("Error parsing gron at line {}: {} does not match root type", line_number, pointer));
+        }
+        for j in 1..segments.len() {
+            let parent = &pointer[..segments[..j].iter().map(|s| s.len() + 1).sum()];
+            let child_is_index = is_index(segments[j]);
+            if let Some(index) = indexes.get(parent) {
+                let parent_type = &entries[*index].pointer.value_type;
+                let compatible = match parent_type {
+                    ValueType::Array(_) => child_is_index,
+                    ValueType::Object(_, _) => true,
+                    _ => false,
+                };
+                if !compatible {
+                    return Err(format!("Error parsing gron at line {}: {} is nested in a value which can't contain it", line_number, pointer));
+                }
+            } else {
+                let value_type = if child_is_index { ValueType::Array(0) } else { ValueType::Object(true, 0) };
+                push_entry(&mut entries, &mut indexes, parent, value_type, None);
+            }
+        }
+
+        if let Some(index) = indexes.get(pointer) {
+            let existing = &entries[*index];
+            let same_container = match (&existing.pointer.value_type, &value_type, &value) {
+                (ValueType::Array(_), ValueType::Array(_), None) => existing.value.is_none(),
+                (ValueType::Object(true, _), ValueType::Object(true, _), _) => true,
+                _ => false,
+            };
+            if !same_container {
+                return Err(format!("Error parsing gron at line {}: {} is defined more than once", line_number, pointer));
+            }
+            continue;
+        }
+        push_entry(&mut entries, &mut indexes, pointer, value_type, value);
+    }
+
+    // Children counts are only known once all lines are read
+    let mut counts = vec![0usize; entries.len()];
+    for entry in entries.iter().filter(|e| !e.pointer.pointer.is_empty()) {
+        let pointer = entry.pointer.pointer.as_str();
+        if let Some(index) = indexes.get(&pointer[..pointer.rfind('/').unwrap()]) {
+            counts[*index] += 1;
+        }
+    }
+    for (entry, count) in entries.iter_mut().zip(counts) {
+        match entry.pointer.value_type {
+            ValueType::Array(_) if entry.value.is_none() => entry.pointer.value_type = ValueType::Array(count),
+            ValueType::Object(true, _) => entry.pointer.value_type = ValueType::Object(true, count),
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+// Pointer and value of a line, quoted pointers are unescaped
+fn split_line(line: &str) -> Option<(Cow<'_, str>, &str)> {
+    if let Some(quoted) = line.strip_prefix('"') {
+        let mut escaped = false;
+        let (end, _) = quoted.char_indices().find(|(_, c)| {
+            let end = !escaped && *c == '"';
+            escaped = !escaped && *c == '\\';
+            end
+        })?;
+        let literal = quoted[end + 1..].strip_prefix(" = ")?;
+        return Some((unescape(&quoted[..end]), literal));
+    }
+    let separator = line.find(" = ")?;
+    Some((Cow::Borrowed(&line[..separator]), &line[separator + 3..]))
+}
+
+fn declare_root(entries: &mut Vec<FlatJsonValue<String>>, indexes: &mut HashMap<String, usize>, root_is_array: &mut Option<bool>, is_array: bool) {
+    *root_is_array = Some(is_array);
+    if is_array {
+        push_entry(entries, indexes, "", ValueType::Array(0), None);
+    }
+}
+
+fn push_entry(entries: &mut Vec<FlatJsonValue<String>>, indexes: &mut HashMap<String, usize>, pointer: &str, value_type: ValueType, value: Option<String>) {
+    let depth = pointer.bytes().filter(|b| *b == b'/').count().max(1) as u8;
+    let position = entries.len();
+    indexes.insert(pointer.to_string(), position);
+    entries.push(FlatJsonValue { pointer: PointerKey { pointer: pointer.to_string(), value_type, depth, position, column_id: 0, span: None, key_span: None, comments: None }, value });
+}
+
+fn parse_literal(literal: &str) -> Option<(ValueType, Option<String>)> {
+    match literal {
+        "{}" => Some((ValueType::Object(true, 0), None)),
+        "[]" => Some((ValueType::Array(0), None)),
+        "null" => Some((ValueType::Null, None)),
+        "true" | "false" => Some((ValueType::Bool, Some(literal.to_string()))),
+        _ if literal.len() >= 2 && literal.starts_with('"') && literal.ends_with('"') => {
+            Some((ValueType::String, Some(literal[1..literal.len() - 1].to_string())))
+        }
+        _ if literal.starts_with('{') && literal.ends_with('}') => Some((ValueType::Object(false, 0), Some(literal.to_string()))),
+        _ if literal.starts_with('[') && literal.ends_with(']') => Some((ValueType::Array(0), Some(literal.to_string()))),
+        _ if is_json_number(literal) => Some((ValueType::Number, Some(literal.to_string()))),
+        _ => None,
+    }
+}
+
+#[inline]
+fn is_index(segment: &str) -> bool {
+    !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gron::{parse_gron, write_gron};
+    use crate::ValueType;
+
+    #[test]
+    fn parse_creates_missing_parents() {
+        let entries = parse_gron("/a/b/0 = \"x\"\n\n/a/b/1 = 2\n/a/c = {\"d\": [1]}\n").unwrap();
+        let pointers: Vec<(&str, &ValueType, u8)> = entries.iter().map(|e| (e.pointer.pointer.as_str(), &e.pointer.value_type, e.pointer.depth)).collect();
+        assert_eq!(pointers, vec![("/a", &ValueType::Object(true, 2), 1), ("/a/b", &ValueType::Array(2), 2),
+                                  ("/a/b/0", &ValueType::String, 3), ("/a/b/1", &ValueType::Number, 3),
+                                  ("/a/c", &ValueType::Object(false, 0), 2)]);
+        assert_eq!(entries[2].value.as_deref(), Some("x"));
+        assert_eq!(entries[4].value.as_deref(), Some("{\"d\": [1]}"));
+        assert!(entries.iter().enumerate().all(|(i, e)| e.pointer.position == i));
+
+        let entries = parse_gron("/0/name = \"a\"\n/1 = {}\n/1/name = \"b\"").unwrap();
+        assert_eq!(entries[0].pointer.pointer, "");
+        assert_eq!(entries[0].pointer.value_type, ValueType::Array(2));
+        assert_eq!(entries[1].pointer.value_type, ValueType::Object(true, 1));
+        assert_eq!(entries[1].pointer.depth, 1);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_lines() {
+        assert_eq!(parse_gron("/a = 1\n/a = 2").err().unwrap(), "Error parsing gron at line 2: /a is defined more than once");
+        assert_eq!(parse_gron("/a = 1\n/a/b = 2").err().unwrap(), "Error parsing gron at line 2: /a/b is nested in a value which can't contain it");
+        assert_eq!(parse_gron("/a = []\n/a/b = 2").err().unwrap(), "Error parsing gron at line 2: /a/b is nested in a value which can't contain it");
+        assert_eq!(parse_gron("/a = 01").err().unwrap(), "Error parsing gron at line 1: invalid value");
+        assert_eq!(parse_gron("/a: 1").err().unwrap(), "Error parsing gron at line 1: expected `pointer = value`");
+        assert_eq!(parse_gron("/0 = 1\n/a = 2").err().unwrap(), "Error parsing gron at line 2: /a does not match root type");
+        assert!(parse_gron("/a/b = 1\n/a = {}").is_ok());
+    }
+
+    #[test]
+    fn write_lines() {
+        let result = crate::JSONParser::parse(r#"[{"a": "x \"y\"", "b": [1, null], "c": {}}]"#, crate::ParseOptions::default()).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_gron(&result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), " = []\n/0 = {}\n/0/a = \"x \\\"y\\\"\"\n/0/b = []\n/0/b/0 = 1\n/0/b/1 = null\n/0/c = {}\n");
+    }
+
+    #[test]
+    fn quoted_pointers() {
+        let json = "{\"a = b\": {\"c =\": \"d = e\"}, \"f\\\ng\": 1, \"\\\"h\": 2}";
+        let result = crate::JSONParser::parse(json, crate::ParseOptions::default().dialect(crate::Dialect::Json5)).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_gron(&result, &mut out).unwrap();
+        let gron = String::from_utf8(out).unwrap();
+        assert_eq!(gron, "\"/a = b\" = {}\n\"/a = b/c =\" = \"d = e\"\n\"/f\\\\\\ng\" = 1\n/\\\"h = 2\n");
+        let entries = parse_gron(&gron).unwrap();
+        let pointers: Vec<(&str, Option<&str>)> = entries.iter().map(|e| (e.pointer.pointer.as_str(), e.value.as_deref())).collect();
+        assert_eq!(pointers, vec![("/a = b", None), ("/a = b/c =", Some("d = e")), ("/f\\\ng", Some("1")), ("/\\\"h", Some("2"))]);
+        assert!(parse_gron("\"/a = 1").is_err());
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn round_trip() {
+        use crate::serializer::serialize_to_json;
+        use crate::{JSONParser, ParseOptions};
+
+        let json = std::fs::read_to_string("examples/grafana.json").unwrap();
+        for options in [ParseOptions::default(), ParseOptions::default().max_depth(3).parse_array(false)] {
+            let result = JSONParser::parse(json.as_str(), options).unwrap().to_owned();
+            let mut out: Vec<u8> = Vec::new();
+            write_gron(&result, &mut out).unwrap();
+            let mut entries = parse_gron(std::str::from_utf8(&out).unwrap()).unwrap();
+            let mut expected = result.json.clone();
+            // raw values are written on a single line
+            let without_whitespace = |json: String| json.split_whitespace().collect::<String>();
+            assert_eq!(without_whitespace(serialize_to_json(&mut entries).unwrap().to_json()), without_whitespace(serialize_to_json(&mut expected).unwrap().to_json()));
+        }
+
+        let mut entries = parse_gron("/a/b/0 = \"x\"\n/a/b/1 = 2\n/a/c = true").unwrap();
+        assert_eq!(serialize_to_json(&mut entries).unwrap().to_json_compact(), r#"{"a":{"b":["x",2],"c":true}}"#);
+    }
+}
@@ -1,4 +1,6 @@
-use crate::string_from_bytes;
+use std::mem;
+
+use crate::{string_from_bytes, Dialect};
 
 #[derive(Debug)]
 pub enum Token<'json> {
@@ -12,6 +14,8 @@ pub enum Token<'json> {
     Number(&'json str),
     Boolean(&'json str),
     Null,
+    Identifier(&'json str), // Json5 unquoted key
+    Invalid(&'json str),
 }
 
 
@@ -89,6 +93,10 @@ impl<'json> SliceRead<'json> {
 
 pub struct Lexer<'json> {
     reader: SliceRead<'json>,
+    token_start: usize,
+    dialect: Dialect,
+    newline: bool, // a new line started since the previous token
+    comments: Vec<(&'json str, bool)>, // comments read since they were taken, and whether they start a line
 }
 
 
@@ -97,17 +105,41 @@ const MASK_CLOSE_CURLY: u64 = 0x0101010101010101 * b'}' as u64;
 const MASK_OPEN_SQUARE: u64 = 0x0101010101010101 * b'[' as u64;
 const MASK_CLOSE_SQUARE: u64 = 0x0101010101010101 * b']' as u64;
 const MASK_QUOTE: u64 = 0x0101010101010101 * b'"' as u64;
+const MASK_SINGLE_QUOTE: u64 = 0x0101010101010101 * b'\'' as u64;
+const MASK_SLASH: u64 = 0x0101010101010101 * b'/' as u64;
+
+// Non zero when one of the bytes of `bytes` is the byte repeated in `mask`
+#[inline]
+fn has_byte(bytes: u64, mask: u64) -> u64 {
+    let comparison = mask ^ bytes;
+    (((comparison >> 1) | 0x8080808080808080) - comparison) & 0x8080808080808080
+}
 
 impl<'json> Lexer<'json> {
     pub fn new(input: &'json [u8]) -> Self {
+        Self::with_dialect(input, Dialect::Json)
+    }
+    pub fn with_dialect(input: &'json [u8], dialect: Dialect) -> Self {
         Lexer {
             reader: SliceRead::new(input),
+            token_start: 0,
+            dialect,
+            newline: false,
+            comments: Vec::new(),
         }
     }
 
     pub fn reader_index(&self) -> usize {
         self.reader.index
     }
+    // Offset of the first byte of the last token returned by next_token
+    pub fn token_start(&self) -> usize {
+        self.token_start
+    }
+    // Comments skipped by next_token since last call, with whether they start a line
+    pub fn take_comments(&mut self) -> Vec<(&'json str, bool)> {
+        mem::take(&mut self.comments)
+    }
     pub fn reader(&mut self) -> &SliceRead<'json> {
         &self.reader
     }
@@ -124,6 +156,7 @@ impl<'json> Lexer<'json> {
         }
 
         let mut in_string = false;
+        let mut quote = b'"';
         let mut escaped = false;
 
         while !self.reader.is_at_end() {
@@ -136,7 +169,7 @@ impl<'json> Lexer<'json> {
                 }
                 match ch {
                     b'\\' => escaped = true,
-                    b'"' => in_string = false,
+                    _ if ch == quote => in_string = false,
                     _ => {}
                 }
                 continue;
@@ -154,7 +187,10 @@ impl<'json> Lexer<'json> {
             let high_bit_mask_square_open = (((comparison_square_open >> 1) | 0x8080808080808080) - comparison_square_open) & 0x8080808080808080;
             let high_bit_mask_quote = (((comparison_quote >> 1) | 0x8080808080808080) - comparison_quote) & 0x8080808080808080;
 
-            let combined_mask = high_bit_mask_square_close | high_bit_mask_square_open | high_bit_mask_quote;
+            let mut combined_mask = high_bit_mask_square_close | high_bit_mask_square_open | high_bit_mask_quote;
+            if self.dialect != Dialect::Json {
+                combined_mask |= has_byte(bytes, MASK_SLASH) | has_byte(bytes, MASK_SINGLE_QUOTE);
+            }
 
             if combined_mask == 0 {
                 // No interesting characters in this chunk
@@ -170,8 +206,18 @@ impl<'json> Lexer<'json> {
                 match ch {
                     b'"' => {
                         in_string = true;
+                        quote = ch;
                         break; // Exit chunk processing, will handle string on next iteration
                     }
+                    b'\'' if self.dialect == Dialect::Json5 => {
+                        in_string = true;
+                        quote = ch;
+                        break;
+                    }
+                    b'/' if self.dialect != Dialect::Json => {
+                        self.skip_comment();
+                        break; // comment may end after this chunk
+                    }
                     b'[' => square_close_count += 1,
                     b']' => {
                         if square_close_count == 1 {
@@ -192,6 +238,7 @@ impl<'json> Lexer<'json> {
         let mut curly_close_count = 1;
         let start = self.reader.index - 1;
         let mut in_string = false;
+        let mut quote = b'"';
         let mut escaped = false;
 
         while !self.reader.is_at_end() {
@@ -204,7 +251,7 @@ impl<'json> Lexer<'json> {
                 }
                 match ch {
                     b'\\' => escaped = true,
-                    b'"' => in_string = false,
+                    _ if ch == quote => in_string = false,
                     _ => {}
                 }
                 continue;
@@ -222,7 +269,10 @@ impl<'json> Lexer<'json> {
             let high_bit_mask_curly_open = (((comparison_curly_open >> 1) | 0x8080808080808080) - comparison_curly_open) & 0x8080808080808080;
             let high_bit_mask_quote = (((comparison_quote >> 1) | 0x8080808080808080) - comparison_quote) & 0x8080808080808080;
 
-            let combined_mask = high_bit_mask_curly_close | high_bit_mask_curly_open | high_bit_mask_quote;
+            let mut combined_mask = high_bit_mask_curly_close | high_bit_mask_curly_open | high_bit_mask_quote;
+            if self.dialect != Dialect::Json {
+                combined_mask |= has_byte(bytes, MASK_SLASH) | has_byte(bytes, MASK_SINGLE_QUOTE);
+            }
 
             if combined_mask == 0 {
                 // No interesting characters in this chunk
@@ -238,8 +288,18 @@ impl<'json> Lexer<'json> {
                 match ch {
                     b'"' => {
                         in_string = true;
+                        quote = ch;
                         break; // Exit chunk processing, will handle string on next iteration
                     }
+                    b'\'' if self.dialect == Dialect::Json5 => {
+                        in_string = true;
+                        quote = ch;
+                        break;
+                    }
+                    b'/' if self.dialect != Dialect::Json => {
+                        self.skip_comment();
+                        break; // comment may end after this chunk
+                    }
                     b'{' => curly_close_count += 1,
                     b'}' => {
                         if curly_close_count == 1 {
@@ -261,25 +321,34 @@ impl<'json> Lexer<'json> {
     }
     #[inline]
     pub fn next_token(&mut self) -> Option<Token<'json>> {
+        // comments at start of input start a line
+        self.newline = self.reader.index == 0;
         loop {
-            match self.reader.next()? {
+            let byte = self.reader.next()?;
+            self.token_start = self.reader.index - 1;
+            match byte {
                 b'{' => return Some(Token::CurlyOpen),
                 b'}' => return Some(Token::CurlyClose),
                 b'[' => return Some(Token::SquareOpen),
                 b']' => return Some(Token::SquareClose),
                 b',' => return Some(Token::Comma),
                 b':' => return Some(Token::Colon),
-                b'-' | b'0' | b'1' | b'2' | b'3' | b'4' | b'5' | b'6' | b'7' | b'8' | b'9' => {
-                    let start = self.reader.index - 1;
-                    while let Some(b) = self.reader.next() {
-                        if !((0x30..=0x39).contains(&b) || b == b'.' || b == b'e' || b == b'+' || b == b'-') {
-                            break;
-                        }
+                b'\n' => self.newline = true,
+                b' ' | b'\r' | b'\t' => {}
+                // byte order mark
+                0xEF if self.token_start == 0 && self.reader.match_pattern(&[0xBB, 0xBF]) => {}
+                b'/' if self.dialect != Dialect::Json && self.skip_comment() => {
+                    let comment = string_from_bytes(self.reader.slice_from(self.token_start))?;
+                    // an unterminated block comment runs until end of input
+                    if comment.starts_with("/*") && (comment.len() < 4 || !comment.ends_with("*/")) {
+                        return Some(Token::Invalid(comment));
                     }
-                    self.reader.index -= 1;
-                    let s = string_from_bytes(&self.reader.slice[start..self.reader.index])?;
-                    return Some(Token::Number(s));
+                    self.comments.push((comment.trim_end(), self.newline));
+                    // line comments end with their new line
+                    self.newline |= comment.contains('\n');
                 }
+                b'-' | b'0' | b'1' | b'2' | b'3' | b'4' | b'5' | b'6' | b'7' | b'8' | b'9' => return self.number(byte),
+                b'+' | b'.' if self.dialect == Dialect::Json5 => return self.number(byte),
                 b'"' => {
                     let start = self.reader.index;
                     while !self.reader.is_at_end() {
@@ -300,13 +369,101 @@ impl<'json> Lexer<'json> {
                     let s = string_from_bytes(&self.reader.slice[start..self.reader.index - 1])?;
                     return Some(Token::String(s));
                 }
+                b'\'' if self.dialect == Dialect::Json5 => {
+                    let start = self.reader.index;
+                    while let Some(b) = self.reader.next() {
+                        match b {
+                            b'\\' => {
+                                self.reader.next();
+                            }
+                            b'\'' => break,
+                            _ => {}
+                        }
+                    }
+                    let s = string_from_bytes(&self.reader.slice[start..self.reader.index - 1])?;
+                    return Some(Token::String(s));
+                }
+                // Json5 keywords are read as identifiers, so a key like `nullable` is not split
+                _ if self.dialect == Dialect::Json5 && is_identifier_start(byte) => return self.identifier(),
                 b't' if self.reader.match_pattern(b"rue") => return Some(Token::Boolean(string_from_bytes(&self.reader.slice[self.reader.index - 4..self.reader.index])?)),
                 b'f' if self.reader.match_pattern(b"alse") => return Some(Token::Boolean(string_from_bytes(&self.reader.slice[self.reader.index - 5..self.reader.index])?)),
                 b'n' if self.reader.match_pattern(b"ull") => return Some(Token::Null),
-                _ => {}
+                _ if self.dialect == Dialect::Json => {}
+                _ => return self.invalid(),
             }
         }
     }
+
+    #[inline]
+    fn number(&mut self, first: u8) -> Option<Token<'json>> {
+        let start = self.token_start;
+        if self.dialect == Dialect::Json5 {
+            let digits_start = if first == b'-' || first == b'+' { self.reader.index } else { start };
+            if digits_start != start && (self.reader.match_pattern(b"Infinity") || self.reader.match_pattern(b"NaN")) {
+                return Some(Token::Number(string_from_bytes(self.reader.slice_from(start))?));
+            }
+            if matches!(self.reader.slice.get(digits_start..digits_start + 2), Some(b"0x" | b"0X")) {
+                self.reader.index = digits_start + 2;
+                while self.reader.peek().is_some_and(|b| b.is_ascii_hexdigit()) {
+                    self.reader.index += 1;
+                }
+                return Some(Token::Number(string_from_bytes(self.reader.slice_from(start))?));
+            }
+        }
+        while let Some(b) = self.reader.peek() {
+            if !((0x30..=0x39).contains(&b) || b == b'.' || b == b'e' || b == b'E' || b == b'+' || b == b'-') {
+                break;
+            }
+            self.reader.index += 1;
+        }
+        let s = string_from_bytes(self.reader.slice_from(start))?;
+        Some(Token::Number(s))
+    }
+
+    fn identifier(&mut self) -> Option<Token<'json>> {
+        while self.reader.peek().is_some_and(|b| is_identifier_start(b) || b.is_ascii_digit()) {
+            self.reader.index += 1;
+        }
+        let s = string_from_bytes(self.reader.slice_from(self.token_start))?;
+        Some(match s {
+            "true" | "false" => Token::Boolean(s),
+            "null" => Token::Null,
+            "Infinity" | "NaN" => Token::Number(s),
+            _ => Token::Identifier(s),
+        })
+    }
+
+    // Skip a comment, `/` being already read. Returns false when it does not start a comment.
+    fn skip_comment(&mut self) -> bool {
+        match self.reader.peek() {
+            Some(b'/') => {
+                while self.reader.next().is_some_and(|b| b != b'\n') {}
+                true
+            }
+            Some(b'*') => {
+                self.reader.index += 1;
+                while !self.reader.is_at_end() && !self.reader.match_pattern(b"*/") {
+                    self.reader.index += 1;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Bytes which can't start a token, up to the next delimiter, so errors show what was found
+    fn invalid(&mut self) -> Option<Token<'json>> {
+        while self.reader.peek().is_some_and(|b| !matches!(b, b' ' | b'\n' | b'\r' | b'\t' | b'{' | b'}' | b'[' | b']' | b',' | b':' | b'"')) {
+            self.reader.index += 1;
+        }
+        Some(Token::Invalid(string_from_bytes(self.reader.slice_from(self.token_start))?))
+    }
+}
+
+// Json5 identifiers, non ascii bytes are accepted as part of unicode letters
+#[inline]
+fn is_identifier_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$' || byte >= 0x80
 }
 
 
@@ -1,16 +1,44 @@
 use std::fmt::{Debug};
 use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Range;
 
-use crate::lexer::Lexer;
+use crate::lexer::{Lexer, Token};
 use crate::parser::Parser;
 use crate::serializer::{serialize_to_json, Value};
 
 pub mod parser;
 pub mod lexer;
 pub mod serializer;
+pub mod table;
+pub mod csv;
+pub mod schema;
+pub mod validation;
+pub mod snapshot;
+pub mod gron;
+mod strings;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "parallel")]
+mod parallel;
 
 pub struct JSONParser {}
 
+/// Syntax accepted by the parser.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dialect {
+    /// Bytes which can't start a token are skipped
+    #[default]
+    Json,
+    /// Json with `//` and `/* */` comments and trailing commas, unexpected bytes are errors
+    Jsonc,
+    /// Jsonc with unquoted keys, single-quoted strings, hexadecimal numbers, numbers starting with `+` or `.`, `Infinity` and `NaN`.
+    /// Values keep their source text, e.g `0x1F`, or the content of a single-quoted string as written.
+    /// The serializer writes them as JSON, `0x1F` as `31` and `NaN` or `Infinity` as null, raw data of containers beyond `max_depth` is written as is.
+    Json5,
+}
+
 #[derive(Clone)]
 pub struct ParseOptions {
     pub parse_array: bool,
@@ -19,6 +47,7 @@ pub struct ParseOptions {
     pub start_parse_at: Option<String>,
     pub start_depth: u8,
     pub prefix: Option<String>,
+    pub dialect: Dialect,
 }
 
 impl Default for ParseOptions {
@@ -30,6 +59,7 @@ impl Default for ParseOptions {
             start_parse_at: None,
             start_depth: 1,
             prefix: None,
+            dialect: Dialect::Json,
         }
     }
 }
@@ -60,6 +90,11 @@ impl ParseOptions {
         self.keep_object_raw_data = keep_object_raw_data;
         self
     }
+    /// Syntax of the input for `parse` and `parse_bytes`, line based formats like JSONL are always parsed as Json.
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
 }
 
 pub trait GetBytes {
@@ -106,12 +141,27 @@ impl<V: Debug + Clone + AsRef<str> + GetBytes> Hash for JsonArrayEntries<V> {
 
 
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointerKey {
     pub pointer: String,
     pub value_type: ValueType,
     pub depth: u8,    // depth of the pointed value in the json
     pub position: usize, // position on the original json
     pub column_id: usize, // can be used to map to external object
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub span: Option<Range<usize>>, // bytes of the value in the parsed input, None when not parsed from a single document
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub key_span: Option<Range<usize>>, // bytes of the key including quotes, None for array elements
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub comments: Option<Box<Comments>>, // only Jsonc and Json5 inputs have comments
+}
+
+/// Comments of the input attached to an entry, with their delimiters, e.g `// note` or `/* note */`. They are written back by `serializer::write_jsonc` only.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comments {
+    pub leading: Vec<String>, // on lines before the entry
+    pub trailing: Vec<String>, // after the entry on its line, or before the end of its container when it is the last entry
 }
 
 impl PartialEq<Self> for PointerKey {
@@ -156,67 +206,132 @@ macro_rules! change_depth {
     pub fn $func<'json>(previous_parse_result: &mut ParseResult<$t>, mut parse_options: ParseOptions) -> Result<(), String> {
         let previous_parse_depth = previous_parse_result.parsing_max_depth;
         let previous_max_json_depth = previous_parse_result.max_json_depth;
-        previous_parse_result.parsing_max_depth = parse_options.max_depth;
         if previous_parse_depth < parse_options.max_depth {
-            let previous_len = previous_parse_result.json.len();
-            for i in 0..previous_len {
-                let entry = &previous_parse_result.json[i];
-                let mut should_parse = false;
-                let mut is_object = false;
-                let mut new_depth = entry.pointer.depth;
-                match entry.pointer.value_type {
-                    ValueType::Array(_) => {
-                        should_parse = parse_options.parse_array && entry.pointer.depth - previous_parse_result.depth_after_start_at == previous_parse_depth;
-                        // println!("{}({:?}) - should parse: {} ({} - {} <= {})", entry.pointer.pointer, entry.pointer.value_type, should_parse, entry.pointer.depth, previous_parse_result.depth_after_start_at, previous_parse_depth);
-                        new_depth = entry.pointer.depth + 1;
-                    }
-                    ValueType::Object(parsed, elements_count) => {
-                        should_parse = !parsed && entry.pointer.depth - previous_parse_result.depth_after_start_at <= previous_parse_depth;
-                        // println!("{}({:?}) - should parse: {} (!{} && {} - {} <= {})", entry.pointer.pointer, entry.pointer.value_type, should_parse, parsed, entry.pointer.depth, previous_parse_result.depth_after_start_at, previous_parse_depth);
-                        is_object = true;
-                        new_depth = entry.pointer.depth + 1;
-                    }
-                    _ => {}
+            // Raw values are parsed first, so an error leaves the result untouched
+            let mut parsed_children = Vec::new();
+            for (i, entry) in previous_parse_result.json.iter().enumerate() {
+                let (should_parse, is_object) = match entry.pointer.value_type {
+                    ValueType::Array(_) => (parse_options.parse_array && entry.pointer.depth - previous_parse_result.depth_after_start_at == previous_parse_depth
+                        && !has_children(&previous_parse_result.json, i), false),
+                    ValueType::Object(parsed, _) => (!parsed && entry.pointer.depth - previous_parse_result.depth_after_start_at <= previous_parse_depth, true),
+                    _ => (false, false),
                 };
+                if let (true, Some(v)) = (should_parse, entry.value.as_ref()) {
+                    let mut lexer = Lexer::with_dialect(v.as_bytes(), previous_parse_result.dialect);
+                    let mut parser = Parser::new_for_change_depth(&mut lexer, previous_parse_result.depth_after_start_at, previous_max_json_depth);
+                    parse_options.prefix = Some(entry.pointer.pointer.clone());
+                    parse_options.dialect = previous_parse_result.dialect;
+                    let mut res = $to_owned(parser.parse(&parse_options, entry.pointer.depth + 1)?);
+                    shift_spans(&mut res.json, entry.pointer.span.as_ref());
+                    parsed_children.push((i, is_object, res));
+                }
+            }
 
-                if should_parse {
-                    if let Some(ref v) = entry.value {
-                        let mut lexer = Lexer::new(v.as_bytes());
-                        let mut parser = Parser::new_for_change_depth(&mut lexer, previous_parse_result.depth_after_start_at, previous_max_json_depth);
-                        parse_options.prefix = Some(entry.pointer.pointer.clone());
-                        let res = parser.parse(&parse_options, new_depth).unwrap();
-                        let mut res = $to_owned(res);
-                        if previous_parse_result.max_json_depth < res.max_json_depth {
-                            previous_parse_result.max_json_depth = res.max_json_depth;
-                        }
+            // Children are spliced right after their parent, so json stays in document order
+            let previous_json = mem::take(&mut previous_parse_result.json);
+            let mut json = Vec::with_capacity(previous_json.len());
+            let mut parsed_children = parsed_children.into_iter().peekable();
+            let mut position_offset = 0_usize;
+            let started_parsing_at_index_start = previous_parse_result.started_parsing_at_index_start;
+            let started_parsing_at_index_end = previous_parse_result.started_parsing_at_index_end;
+            for (i, mut entry) in previous_json.into_iter().enumerate() {
+                entry.pointer.position += position_offset;
+                if previous_parse_result.started_parsing_at.is_some() && i == started_parsing_at_index_start {
+                    previous_parse_result.started_parsing_at_index_start = json.len();
+                }
+                let parent_index = json.len();
+                json.push(entry);
+
+                if let Some((_, is_object, mut res)) = parsed_children.next_if(|(index, _, _)| *index == i) {
+                    if previous_parse_result.max_json_depth < res.max_json_depth {
+                        previous_parse_result.max_json_depth = res.max_json_depth;
+                    }
 
-                        // println!("{:?}", res.json);
-                        if res.json.len() > 0 {
-                            match &res.json[0].pointer.value_type {
-                                ValueType::Array(size) => {
-                                    previous_parse_result.json[i].pointer.value_type = ValueType::Array(*size);
-                                    if res.json[0].pointer.pointer.eq("") {
-                                        res.json.swap_remove(0); // remove array empty pointer
-                                    }
-                                }
-                                _ => {}
+                    if !res.json.is_empty() {
+                        if let ValueType::Array(size) = res.json[0].pointer.value_type {
+                            json[parent_index].pointer.value_type = ValueType::Array(size);
+                            if res.json[0].pointer.pointer.is_empty() {
+                                res.json.remove(0); // remove array empty pointer
                             }
                         }
+                    }
 
-                        if is_object {
-                            let root_depth = previous_parse_result.json[i].pointer.depth + 1;
-                            let  elements_count = res.json.iter().filter(|e| e.pointer.depth == root_depth).count();
-                            previous_parse_result.json[i].pointer.value_type = ValueType::Object(true, elements_count);
-                        }
+                    if is_object {
+                        let root_depth = json[parent_index].pointer.depth + 1;
+                        let elements_count = res.json.iter().filter(|e| e.pointer.depth == root_depth).count();
+                        json[parent_index].pointer.value_type = ValueType::Object(true, elements_count);
+                    }
 
-                        previous_parse_result.json.extend(res.json);
+                    // sub parse positions restart at 1, they are made relative to the parent
+                    let parent_position = json[parent_index].pointer.position;
+                    for (j, child) in res.json.iter_mut().enumerate() {
+                        child.pointer.position = parent_position + j + 1;
                     }
+                    position_offset += res.json.len();
+                    json.extend(res.json);
+                }
+                if previous_parse_result.started_parsing_at.is_some() && i == started_parsing_at_index_end {
+                    previous_parse_result.started_parsing_at_index_end = json.len() - 1;
                 }
             }
-            Ok(())
-        } else {
-            Ok(())
+            previous_parse_result.json = json;
+        }
+        previous_parse_result.parsing_max_depth = parse_options.max_depth;
+        Ok(())
+    }
+    )+};
+}
+macro_rules! expand_node {
+    ($($t:ty, $func:ident $(<$lifetime:lifetime>)?, $to_owned:expr),+) => {$(
+    pub fn $func$(<$lifetime>)?(previous_parse_result: &mut ParseResult<$t>, pointer: &str, levels: u8) -> Result<(), String> {
+        let index = previous_parse_result.json.iter().position(|e| e.pointer.pointer.eq(pointer))
+            .ok_or_else(|| format!("No node found at pointer {}", pointer))?;
+        if !matches!(previous_parse_result.json[index].pointer.value_type, ValueType::Object(..) | ValueType::Array(_)) {
+            return Err(format!("Node at pointer {} is neither an object nor an array", pointer));
+        }
+        if previous_parse_result.json[index].value.is_none() {
+            return Err(format!("Node at pointer {} has no raw data to expand", pointer));
+        }
+        if levels == 0 {
+            return Ok(());
+        }
+        let entry_depth = previous_parse_result.json[index].pointer.depth;
+        let parse_options = ParseOptions::default()
+            .max_depth(entry_depth.saturating_add(levels).saturating_sub(previous_parse_result.depth_after_start_at))
+            .prefix(pointer.to_string())
+            .dialect(previous_parse_result.dialect)
+            .parse_array(previous_parse_result.parse_array)
+            .keep_object_raw_data(previous_parse_result.keep_object_raw_data);
+        let mut res = {
+            let v = previous_parse_result.json[index].value.as_ref().unwrap();
+            let mut lexer = Lexer::with_dialect(v.as_bytes(), previous_parse_result.dialect);
+            let mut parser = Parser::new_for_change_depth(&mut lexer, previous_parse_result.depth_after_start_at, previous_parse_result.max_json_depth);
+            $to_owned(parser.parse(&parse_options, entry_depth + 1)?)
+        };
+        shift_spans(&mut res.json, previous_parse_result.json[index].pointer.span.as_ref());
+        // Parsed first, so an error leaves the result untouched
+        previous_parse_result.remove_descendants(index);
+        if previous_parse_result.max_json_depth < res.max_json_depth {
+            previous_parse_result.max_json_depth = res.max_json_depth;
+        }
+
+        match previous_parse_result.json[index].pointer.value_type {
+            ValueType::Array(_) => {
+                if let Some(ValueType::Array(size)) = res.json.first().map(|e| e.pointer.value_type) {
+                    previous_parse_result.json[index].pointer.value_type = ValueType::Array(size);
+                    if res.json[0].pointer.pointer.is_empty() {
+                        res.json.remove(0); // remove array empty pointer
+                    }
+                }
+                // raw data is kept so it can be collapsed again, being followed by its children tells it is parsed
+            }
+            _ => {
+                let elements_count = res.json.iter().filter(|e| e.pointer.depth == entry_depth + 1).count();
+                previous_parse_result.json[index].pointer.value_type = ValueType::Object(true, elements_count);
+            }
         }
+        previous_parse_result.insert_children(index, res.json);
+        Ok(())
     }
     )+};
 }
@@ -229,12 +344,16 @@ impl PointerKey {
             depth,
             position,
             column_id: 0,
+            span: None,
+            key_span: None,
+            comments: None,
         }
     }
 }
 
 #[derive(Eq, Hash, PartialEq, Debug, Clone, Copy)]
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueType {
     Array(usize),
     Object(bool, usize), // parsed or not, number of elements
@@ -251,12 +370,29 @@ type PointerFragment = Vec<String>;
 
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FlatJsonValue<V: Debug + Clone + AsRef<str> + GetBytes> {
     pub pointer: PointerKey,
     pub value: Option<V>,
 }
 
 
+impl<V: Debug + Clone + AsRef<str> + GetBytes> FlatJsonValue<V> {
+    /// Bytes of the entry in the parsed input, from its key (when it has one) to the end of its value
+    pub fn span(&self) -> Option<Range<usize>> {
+        let value = self.pointer.span.as_ref()?;
+        Some(self.pointer.key_span.as_ref().map_or(value.start, |key| key.start)..value.end)
+    }
+    /// Bytes of the key including quotes, None for array elements
+    pub fn key_span(&self) -> Option<Range<usize>> {
+        self.pointer.key_span.clone()
+    }
+    /// Bytes of the value
+    pub fn value_span(&self) -> Option<Range<usize>> {
+        self.pointer.span.clone()
+    }
+}
+
 impl<V: Debug + Clone + AsRef<str> + GetBytes>  Hash for FlatJsonValue<V> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.pointer.hash(state);
@@ -265,6 +401,7 @@ impl<V: Debug + Clone + AsRef<str> + GetBytes>  Hash for FlatJsonValue<V> {
 
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParseResult<V: Debug + Clone + AsRef<str> + GetBytes> {
     pub json: Vec<FlatJsonValue<V>>,
     pub max_json_depth: usize,
@@ -274,6 +411,81 @@ pub struct ParseResult<V: Debug + Clone + AsRef<str> + GetBytes> {
     pub started_parsing_at_index_end: usize,
     pub parsing_prefix: Option<String>,
     pub depth_after_start_at: u8,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dialect: Dialect, // raw values are parsed again with it when depth changes
+    #[cfg_attr(feature = "serde", serde(default = "default_true"))]
+    pub parse_array: bool, // options parsed with, also used to parse raw values when depth changes
+    #[cfg_attr(feature = "serde", serde(default = "default_true"))]
+    pub keep_object_raw_data: bool,
+}
+
+#[cfg(feature = "serde")]
+fn default_true() -> bool {
+    true
+}
+
+impl<V: Debug + Clone + AsRef<str> + GetBytes> ParseResult<V> {
+    /// Insert `children` right after the entry at `parent_index`. Positions of following entries are shifted so they stay ordered.
+    fn insert_children(&mut self, parent_index: usize, mut children: Vec<FlatJsonValue<V>>) {
+        let children_len = children.len();
+        if children_len == 0 {
+            return;
+        }
+        let parent_position = self.json[parent_index].pointer.position;
+        for entry in self.json.iter_mut() {
+            if entry.pointer.position > parent_position {
+                entry.pointer.position += children_len;
+            }
+        }
+        for (i, child) in children.iter_mut().enumerate() {
+            child.pointer.position = parent_position + i + 1;
+        }
+        self.json.splice(parent_index + 1..parent_index + 1, children);
+
+        if self.started_parsing_at.is_some() {
+            if parent_index < self.started_parsing_at_index_start {
+                self.started_parsing_at_index_start += children_len;
+                self.started_parsing_at_index_end += children_len;
+            } else if parent_index <= self.started_parsing_at_index_end {
+                self.started_parsing_at_index_end += children_len;
+            }
+        }
+    }
+
+    /// Index of the deepest entry whose span contains byte `offset` of the parsed input, e.g to select the entry under a text cursor
+    pub fn entry_at(&self, offset: usize) -> Option<usize> {
+        // Spans containing offset are nested and parents come first in document order, the last one is the deepest
+        self.json.iter().rposition(|entry| entry.span().is_some_and(|span| span.contains(&offset)))
+    }
+
+    /// Remove all entries located under the entry at `parent_index`, returns the number of removed entries.
+    fn remove_descendants(&mut self, parent_index: usize) -> usize {
+        let prefix = concat_string!(self.json[parent_index].pointer.pointer, "/");
+        let mut removed_before_start = 0;
+        let mut removed_before_end = 0;
+        let mut i = 0;
+        let started_parsing_at_index_start = self.started_parsing_at_index_start;
+        let started_parsing_at_index_end = self.started_parsing_at_index_end;
+        let len = self.json.len();
+        self.json.retain(|e| {
+            let keep = !e.pointer.pointer.starts_with(prefix.as_str());
+            if !keep {
+                if i < started_parsing_at_index_start {
+                    removed_before_start += 1;
+                }
+                if i <= started_parsing_at_index_end {
+                    removed_before_end += 1;
+                }
+            }
+            i += 1;
+            keep
+        });
+        if self.started_parsing_at.is_some() {
+            self.started_parsing_at_index_start -= removed_before_start;
+            self.started_parsing_at_index_end -= removed_before_end;
+        }
+        len - self.json.len()
+    }
 }
 
 impl ParseResult<String> {
@@ -287,6 +499,9 @@ impl ParseResult<String> {
             started_parsing_at_index_end: self.started_parsing_at_index_end,
             parsing_prefix: self.parsing_prefix.clone(),
             depth_after_start_at: self.depth_after_start_at,
+            dialect: self.dialect,
+            parse_array: self.parse_array,
+            keep_object_raw_data: self.keep_object_raw_data,
         }
     }
 
@@ -306,6 +521,9 @@ impl ParseResult<&str> {
             started_parsing_at: self.started_parsing_at.clone(),
             parsing_prefix: self.parsing_prefix.clone(),
             depth_after_start_at: self.depth_after_start_at,
+            dialect: self.dialect,
+            parse_array: self.parse_array,
+            keep_object_raw_data: self.keep_object_raw_data,
         }
     }
     pub fn to_owned(self) -> ParseResult<String> {
@@ -323,6 +541,9 @@ impl ParseResult<&str> {
             started_parsing_at: self.started_parsing_at.clone(),
             parsing_prefix: self.parsing_prefix.clone(),
             depth_after_start_at: self.depth_after_start_at,
+            dialect: self.dialect,
+            parse_array: self.parse_array,
+            keep_object_raw_data: self.keep_object_raw_data,
         }
     }
 
@@ -334,7 +555,7 @@ impl JSONParser {
         JSONParser::parse_bytes(input.as_bytes(), options)
     }
     pub fn parse_bytes(input: &[u8], options: ParseOptions) -> Result<ParseResult<&str>, String> {
-        let mut lexer = Lexer::new(input);
+        let mut lexer = Lexer::with_dialect(input, options.dialect);
         let mut parser = Parser::new(&mut lexer);
         parser.parse(&options, options.start_depth)
     }
@@ -343,36 +564,53 @@ impl JSONParser {
     change_depth!(&'json str, change_depth, |r: ParseResult<&'json str>| r);
     change_depth!(String, change_depth_owned, |r: ParseResult<&str>| r.to_owned());
 
+    // Parse `levels` levels below the node at `pointer` from its raw value with the options of the result, other nodes are left untouched
+    expand_node!(&'json str, expand_node<'json>, |r: ParseResult<&'json str>| r);
+    expand_node!(String, expand_node_owned, |r: ParseResult<&str>| r.to_owned());
+
+    // Remove children of the node at `pointer`, node keeps its raw value so it can be expanded again.
+    // Arrays parsed by the parser do not keep their raw value and can't be collapsed, expanded ones can.
+    pub fn collapse_node<V: Debug + Clone + AsRef<str> + GetBytes>(previous_parse_result: &mut ParseResult<V>, pointer: &str) -> Result<(), String> {
+        let index = previous_parse_result.json.iter().position(|e| e.pointer.pointer.eq(pointer))
+            .ok_or_else(|| format!("No node found at pointer {}", pointer))?;
+        let entry = &mut previous_parse_result.json[index];
+        match entry.pointer.value_type {
+            ValueType::Object(..) | ValueType::Array(_) if entry.value.is_none() => {
+                return Err(format!("Node at pointer {} has no raw data, it can't be collapsed", pointer));
+            }
+            ValueType::Object(..) => entry.pointer.value_type = ValueType::Object(false, 0),
+            ValueType::Array(_) => {}
+            _ => return Err(format!("Node at pointer {} is neither an object nor an array", pointer)),
+        }
+        previous_parse_result.remove_descendants(index);
+        Ok(())
+    }
 
-    pub fn serialize<'a>(data: &mut Vec<FlatJsonValue<&'a str>>) -> Value<&'a str> {
+
+    pub fn serialize<'a>(data: &mut Vec<FlatJsonValue<&'a str>>) -> Result<Value<&'a str>, String> {
         serialize_to_json(data)
     }
 
-    pub fn serialize_owned(data: &mut Vec<FlatJsonValue<String>>) -> Value<String> {
+    pub fn serialize_owned(data: &mut Vec<FlatJsonValue<String>>) -> Result<Value<String>, String> {
         serialize_to_json(data)
     }
 
     pub fn is_jsonl(input: &[u8]) -> bool {
 
-        // Content heuristic: look for }\n{ or }\r\n{ pattern in first 4KB
-        // Skip leading whitespace
-        let mut i = 0;
-        while i < input.len() && input[i].is_ascii_whitespace() {
-            i += 1;
-        }
-
-        // Must start with { to be JSONL (not array)
-        if i >= input.len() || input[i] != b'{' {
-            return false;
-        }
-
-        // Look for }\n{ or }\r\n{ pattern
+        // Content heuristic: look in first 4KB for a complete top level value followed by a newline and another value.
+        // A single JSON document can only have one top level value.
         let check_len = input.len().min(4096);
+        let mut i = 0;
+        let mut depth = 0_usize;
         let mut in_string = false;
+        let mut in_scalar = false;
         let mut escaped = false;
+        let mut value_end = false;
+        let mut newline_after_value = false;
 
         while i < check_len {
             let ch = input[i];
+            i += 1;
 
             if in_string {
                 if escaped {
@@ -381,27 +619,54 @@ impl JSONParser {
                     escaped = true;
                 } else if ch == b'"' {
                     in_string = false;
+                    if depth == 0 {
+                        value_end = true;
+                    }
                 }
-                i += 1;
                 continue;
             }
 
+            if in_scalar {
+                if ch.is_ascii_alphanumeric() || ch == b'.' || ch == b'+' || ch == b'-' {
+                    continue;
+                }
+                in_scalar = false;
+                value_end = true;
+            }
+
             match ch {
-                b'"' => in_string = true,
-                b'}' => {
-                    // Check for }\n{ or }\r\n{
-                    if i + 2 < check_len {
-                        if input[i + 1] == b'\n' && input[i + 2] == b'{' {
-                            return true;
+                b'\n' => {
+                    if depth == 0 && value_end {
+                        newline_after_value = true;
+                    }
+                }
+                b' ' | b'\t' | b'\r' => {}
+                b'}' | b']' => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        value_end = true;
+                    }
+                }
+                _ => {
+                    if depth == 0 {
+                        if value_end {
+                            // a second top level value on a new line
+                            return newline_after_value;
+                        }
+                        match ch {
+                            b'{' | b'[' => depth += 1,
+                            b'"' => in_string = true,
+                            _ => in_scalar = true,
                         }
-                        if i + 3 < check_len && input[i + 1] == b'\r' && input[i + 2] == b'\n' && input[i + 3] == b'{' {
-                            return true;
+                    } else {
+                        match ch {
+                            b'{' | b'[' => depth += 1,
+                            b'"' => in_string = true,
+                            _ => {}
                         }
                     }
                 }
-                _ => {}
             }
-            i += 1;
         }
 
         false
@@ -411,7 +676,6 @@ impl JSONParser {
         let mut all_values: Vec<FlatJsonValue<String>> = Vec::with_capacity(1024);
         let mut row_index = 0_usize;
         let mut max_depth = 0_usize;
-        let mut line_number = 0_usize;
 
         // Root array pointer (will update size at the end)
         all_values.push(FlatJsonValue {
@@ -419,75 +683,177 @@ impl JSONParser {
             value: None,
         });
 
-        // Split by newlines and process each line
-        let mut line_start = 0;
-        for (i, &byte) in input.iter().enumerate() {
-            if byte == b'\n' || i == input.len() - 1 {
-                let line_end = if byte == b'\n' { i } else { i + 1 };
-                let line = &input[line_start..line_end];
-                line_number += 1;
-
-                // Trim whitespace and skip empty lines
-                let trimmed = trim_ascii_whitespace(line);
-                if !trimmed.is_empty() {
-                    // Parse this line as a JSON object
-                    let line_options = ParseOptions {
-                        parse_array: options.parse_array,
-                        keep_object_raw_data: options.keep_object_raw_data,
-                        max_depth: options.max_depth,
-                        start_parse_at: None,
-                        start_depth: 2, // Objects are at depth 2 (root array is depth 1)
-                        prefix: Some(format!("/{}", row_index)),
-                    };
-
-                    match Self::parse_bytes(trimmed, line_options) {
-                        Ok(line_result) => {
-                            max_depth = max_depth.max(line_result.max_json_depth);
-
-                            // Count root-level elements for this object
-                            let elements_count = line_result.json.iter()
-                                .filter(|e| e.pointer.depth == 2)
-                                .count();
-
-                            // Add the root object entry for this row with raw content
-                            let raw_content = if options.keep_object_raw_data {
-                                string_from_bytes(trimmed).map(|s| s.to_owned())
-                            } else {
-                                None
-                            };
-                            all_values.push(FlatJsonValue {
-                                pointer: PointerKey::from_pointer(
-                                    format!("/{}", row_index),
-                                    ValueType::Object(true, elements_count),
-                                    2,
-                                    row_index + 1,
-                                ),
-                                value: raw_content,
-                            });
-
-                            // Convert &str values to String and extend with parsed fields
-                            for entry in line_result.json {
-                                all_values.push(FlatJsonValue {
-                                    pointer: entry.pointer,
-                                    value: entry.value.map(|s| s.to_owned()),
-                                });
-                            }
-                            row_index += 1;
-                        }
-                        Err(e) => {
-                            return Err(format!("Error parsing JSONL at line {}: {}", line_number, e));
-                        }
+        for (line_number, _, line) in JsonlLines::new(input) {
+            match Self::parse_jsonl_row(line, row_index, &options, &mut all_values) {
+                Ok(row_max_depth) => {
+                    max_depth = max_depth.max(row_max_depth);
+                    row_index += 1;
+                }
+                Err(e) => {
+                    return Err(format!("Error parsing JSONL at line {}: {}", line_number, e));
+                }
+            }
+        }
+
+        Ok(Self::jsonl_result(all_values, row_index, max_depth, &options))
+    }
+
+    /// Same as `parse_jsonl` but lines which fail to parse are skipped and returned alongside the result.
+    /// Parsing is aborted once more than `max_errors` lines failed.
+    pub fn parse_jsonl_lenient(input: &[u8], options: ParseOptions, max_errors: usize) -> Result<(ParseResult<String>, Vec<LineError>), String> {
+        let mut all_values: Vec<FlatJsonValue<String>> = Vec::with_capacity(1024);
+        let mut errors: Vec<LineError> = Vec::new();
+        let mut row_index = 0_usize;
+        let mut max_depth = 0_usize;
+
+        all_values.push(FlatJsonValue {
+            pointer: PointerKey::from_pointer(String::new(), ValueType::Array(0), 1, 0),
+            value: None,
+        });
+
+        for (line_number, byte_offset, line) in JsonlLines::new(input) {
+            match Self::parse_jsonl_row(line, row_index, &options, &mut all_values) {
+                Ok(row_max_depth) => {
+                    max_depth = max_depth.max(row_max_depth);
+                    row_index += 1;
+                }
+                Err(e) => {
+                    if errors.len() >= max_errors {
+                        return Err(format!("Too many errors parsing JSONL, more than {} lines failed, last one at line {}: {}", max_errors, line_number, e));
                     }
+                    errors.push(LineError {
+                        line: line_number,
+                        byte_offset,
+                        error: e,
+                        raw: String::from_utf8_lossy(line).into_owned(),
+                    });
+                }
+            }
+        }
+
+        Ok((Self::jsonl_result(all_values, row_index, max_depth, &options), errors))
+    }
+
+    /// Parse a single JSONL line as the row `/row_index`, the row entry and its children are pushed into `target`.
+    /// Returns max depth of the row.
+    fn parse_jsonl_row(line: &[u8], row_index: usize, options: &ParseOptions, target: &mut Vec<FlatJsonValue<String>>) -> Result<usize, String> {
+        let element = split_row(line)?;
+        Self::push_row(row_index, element, options, target)
+    }
+
+    fn push_row(row_index: usize, element: ArrayElement, options: &ParseOptions, target: &mut Vec<FlatJsonValue<String>>) -> Result<usize, String> {
+        let mut row: Vec<FlatJsonValue<&str>> = Vec::with_capacity(16);
+        let max_depth = parse_row(row_index, element, options, &mut row)?;
+        // Convert &str values to String
+        for entry in row {
+            target.push(FlatJsonValue {
+                pointer: entry.pointer,
+                value: entry.value.map(|s| s.to_owned()),
+            });
+        }
+        Ok(max_depth)
+    }
+
+    /// Parse a JSON text sequence (RFC 7464, `application/json-seq`): each value is preceded by a record separator (0x1E).
+    /// Values are laid out like `parse_jsonl` rows.
+    pub fn parse_json_seq(input: &[u8], options: ParseOptions) -> Result<ParseResult<String>, String> {
+        let mut all_values: Vec<FlatJsonValue<String>> = Vec::with_capacity(1024);
+        let mut row_index = 0_usize;
+        let mut max_depth = 0_usize;
+
+        all_values.push(FlatJsonValue {
+            pointer: PointerKey::from_pointer(String::new(), ValueType::Array(0), 1, 0),
+            value: None,
+        });
+
+        for (record_number, record) in input.split(|b| *b == RECORD_SEPARATOR).enumerate() {
+            let record = trim_ascii_whitespace(record);
+            if record.is_empty() {
+                continue;
+            }
+            if record_number == 0 {
+                return Err("Expected JSON text sequence to start with a record separator".to_string());
+            }
+            match split_row(record).and_then(|element| Self::push_row(row_index, element, &options, &mut all_values)) {
+                Ok(row_max_depth) => {
+                    max_depth = max_depth.max(row_max_depth);
+                    row_index += 1;
                 }
+                Err(e) => {
+                    return Err(format!("Error parsing JSON text sequence at record {}: {}", record_number, e));
+                }
+            }
+        }
+
+        Ok(Self::jsonl_result(all_values, row_index, max_depth, &options))
+    }
+
+    /// Parse JSON values concatenated without separator (e.g `{"a":1}{"a":2}[3]`), as produced by some streaming APIs.
+    /// Values are laid out like `parse_jsonl` rows.
+    pub fn parse_concatenated(input: &[u8], options: ParseOptions) -> Result<ParseResult<String>, String> {
+        let mut all_values: Vec<FlatJsonValue<String>> = Vec::with_capacity(1024);
+        let mut row_index = 0_usize;
+        let mut max_depth = 0_usize;
 
-                line_start = i + 1;
+        all_values.push(FlatJsonValue {
+            pointer: PointerKey::from_pointer(String::new(), ValueType::Array(0), 1, 0),
+            value: None,
+        });
+
+        // Lexer silently skips bytes it does not know, only whitespace is accepted between values
+        let unexpected_content = |content: &[u8]| if content.iter().all(u8::is_ascii_whitespace) {
+            Ok(())
+        } else {
+            Err(format!("Unexpected content after value: {}", String::from_utf8_lossy(trim_ascii_whitespace(content))))
+        };
+        let mut lexer = Lexer::new(input);
+        let mut value_end = if input.starts_with(&[0xEF, 0xBB, 0xBF]) { 3 } else { 0 };
+        while let Some(token) = lexer.next_token() {
+            let element = unexpected_content(&input[value_end..lexer.token_start()]).and_then(|_| match token {
+                Token::CurlyOpen => lexer.consume_string_until_end_of_object(true).map(ArrayElement::Object)
+                    .ok_or_else(|| "Unterminated object".to_string()),
+                Token::SquareOpen => lexer.consume_string_until_end_of_array(lexer.reader_index() - 1, false).map(ArrayElement::Array)
+                    .ok_or_else(|| "Unterminated array".to_string()),
+                Token::String(_) if input[lexer.reader_index() - 1] != b'"' || lexer.reader_index() - lexer.token_start() < 2 => Err("Unterminated string".to_string()),
+                Token::String(_) | Token::Number(_) | Token::Boolean(_) | Token::Null => Ok(ArrayElement::Scalar(token)),
+                _ => Err(format!("Unexpected token: {:?}", token)),
+            });
+            value_end = lexer.reader_index();
+            match element.and_then(|element| Self::push_row(row_index, element, &options, &mut all_values)) {
+                Ok(row_max_depth) => {
+                    max_depth = max_depth.max(row_max_depth);
+                    row_index += 1;
+                }
+                Err(e) => {
+                    return Err(format!("Error parsing concatenated JSON at value {}: {}", row_index, e));
+                }
             }
         }
+        unexpected_content(&input[value_end..]).map_err(|e| format!("Error parsing concatenated JSON at value {}: {}", row_index, e))?;
+
+        Ok(Self::jsonl_result(all_values, row_index, max_depth, &options))
+    }
 
+    fn jsonl_result(mut all_values: Vec<FlatJsonValue<String>>, rows_count: usize, max_depth: usize, options: &ParseOptions) -> ParseResult<String> {
         // Update array size in root pointer
-        all_values[0].pointer.value_type = ValueType::Array(row_index);
+        all_values[0].pointer.value_type = ValueType::Array(rows_count);
+        // Rows are parsed independently, they are given the layout parse_jsonl always had once stitched together:
+        // rows at depth 2 with position row index + 1, entries of a row with positions starting at 1 within it
+        let mut row_index = 0;
+        let mut position_in_row = 0;
+        for entry in all_values.iter_mut().skip(1) {
+            if entry.pointer.depth == 1 {
+                row_index += 1;
+                position_in_row = 0;
+                entry.pointer.depth = 2;
+                entry.pointer.position = row_index;
+            } else {
+                position_in_row += 1;
+                entry.pointer.position = position_in_row;
+            }
+        }
 
-        Ok(ParseResult {
+        ParseResult {
             max_json_depth: max_depth,
             parsing_max_depth: options.max_depth,
             started_parsing_at: None,
@@ -496,7 +862,181 @@ impl JSONParser {
             json: all_values,
             parsing_prefix: None,
             depth_after_start_at: 0,
-        })
+            dialect: Dialect::Json,
+            parse_array: options.parse_array,
+            keep_object_raw_data: options.keep_object_raw_data,
+        }
+    }
+}
+
+// Root array element or row, as found by skipping over the input without parsing it
+enum ArrayElement<'json> {
+    Object(&'json str),
+    Array(&'json str),
+    Scalar(Token<'json>),
+}
+
+/// Push entries of root array element `/index` into `target`, the same way parser does when it parses root array.
+/// `span` is the range of the element in the input, spans are dropped without it.
+/// Returns max depth of the element.
+fn parse_array_element<'json>(index: usize, element: ArrayElement<'json>, span: Option<Range<usize>>, options: &ParseOptions, target: &mut Vec<FlatJsonValue<&'json str>>) -> Result<usize, String> {
+    // Root array elements are at depth 1, their children at depth 2
+    if options.max_depth < 1 {
+        return Ok(0);
+    }
+    let pointer = format!("/{}", index);
+    // Element is parsed on its own, whatever the root was started at
+    let element_options = ParseOptions { start_parse_at: None, start_depth: 2, prefix: Some(pointer.clone()), ..options.clone() };
+    match element {
+        ArrayElement::Object(raw) => {
+            let mut lexer = Lexer::new(raw.as_bytes());
+            let mut parser = Parser::new(&mut lexer);
+            let mut res = parser.parse(&element_options, 2)?;
+            shift_spans(&mut res.json, span.as_ref());
+            if options.keep_object_raw_data || options.max_depth == 1 {
+                target.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::Object(1 < options.max_depth, 0), 1, 0), value: Some(raw) });
+            } else {
+                target.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::Object(true, 0), 1, 0), value: None });
+            }
+            let object_index = target.len() - 1;
+            target[object_index].pointer.span = span;
+            if let ValueType::Object(true, _) = target[object_index].pointer.value_type {
+                let elements_count = res.json.iter().filter(|e| e.pointer.depth == 2).count();
+                target[object_index].pointer.value_type = ValueType::Object(true, elements_count);
+            }
+            target.extend(res.json);
+            Ok(res.max_json_depth)
+        }
+        ArrayElement::Array(raw) => {
+            let mut lexer = Lexer::new(raw.as_bytes());
+            let mut parser = Parser::new(&mut lexer);
+            let mut res = parser.parse(&element_options, 2)?;
+            shift_spans(&mut res.json, span.as_ref());
+            let mut entries = res.json.into_iter();
+            // First entry is the array itself, with an empty pointer
+            if let Some(mut array) = entries.next() {
+                array.pointer.pointer = pointer;
+                array.pointer.depth = 1;
+                target.push(array);
+            }
+            target.extend(entries);
+            Ok(res.max_json_depth)
+        }
+        ArrayElement::Scalar(token) => {
+            let (value_type, value) = match token {
+                Token::String(value) => (ValueType::String, Some(value)),
+                Token::Number(value) => (ValueType::Number, Some(value)),
+                Token::Boolean(value) => (ValueType::Bool, Some(value)),
+                _ => (ValueType::Null, None),
+            };
+            let mut pointer = PointerKey::from_pointer(pointer, value_type, 1, 0);
+            pointer.span = span;
+            target.push(FlatJsonValue { pointer, value });
+            Ok(0)
+        }
+    }
+}
+
+/// Whether the entry at `index` is followed by its children. Parsed arrays may keep their raw data, e.g expanded nodes or JSONL array rows.
+pub(crate) fn has_children<V: Debug + Clone + AsRef<str> + GetBytes>(entries: &[FlatJsonValue<V>], index: usize) -> bool {
+    let pointer = entries[index].pointer.pointer.as_str();
+    entries.get(index + 1).is_some_and(|next| next.pointer.pointer.len() > pointer.len()
+        && next.pointer.pointer.starts_with(pointer) && next.pointer.pointer.as_bytes()[pointer.len()] == b'/')
+}
+
+// Entries parsed from the raw value of a parent have spans relative to it, they are made relative to the input of the parent.
+// Without parent span (e.g rows parsed independently), spans are dropped.
+fn shift_spans<V: Debug + Clone + AsRef<str> + GetBytes>(entries: &mut [FlatJsonValue<V>], parent_span: Option<&Range<usize>>) {
+    let shift = |span: Option<Range<usize>>| match (parent_span, span) {
+        (Some(parent), Some(span)) => Some(parent.start + span.start..parent.start + span.end),
+        _ => None,
+    };
+    for entry in entries.iter_mut() {
+        entry.pointer.span = shift(entry.pointer.span.take());
+        entry.pointer.key_span = shift(entry.pointer.key_span.take());
+    }
+}
+
+/// Rows are laid out as elements of a root array, except that array rows also keep their raw data when `keep_object_raw_data` is set.
+fn parse_row<'json>(row_index: usize, element: ArrayElement<'json>, options: &ParseOptions, target: &mut Vec<FlatJsonValue<&'json str>>) -> Result<usize, String> {
+    let raw_array = match element {
+        ArrayElement::Array(raw) if options.keep_object_raw_data => Some(raw),
+        _ => None,
+    };
+    let row_index_in_target = target.len();
+    let max_depth = parse_array_element(row_index, element, None, options, target)?;
+    if raw_array.is_some() && row_index_in_target < target.len() {
+        target[row_index_in_target].value = raw_array;
+    }
+    Ok(max_depth)
+}
+
+const RECORD_SEPARATOR: u8 = 0x1E;
+
+/// Check `line` contains exactly one complete JSON value.
+/// Parser stops silently at end of input, so truncated rows are detected here before parsing.
+fn split_row(line: &[u8]) -> Result<ArrayElement<'_>, String> {
+    let mut lexer = Lexer::new(line);
+    let element = match lexer.next_token() {
+        Some(Token::CurlyOpen) => ArrayElement::Object(lexer.consume_string_until_end_of_object(true).ok_or_else(|| "Unterminated object".to_string())?),
+        Some(Token::SquareOpen) => ArrayElement::Array(lexer.consume_string_until_end_of_array(0, false).ok_or_else(|| "Unterminated array".to_string())?),
+        Some(Token::String(value)) => {
+            if value.len() + 2 > line.len() || line[line.len() - 1] != b'"' {
+                return Err("Unterminated string".to_string());
+            }
+            ArrayElement::Scalar(Token::String(value))
+        }
+        Some(token @ (Token::Number(_) | Token::Boolean(_) | Token::Null)) => ArrayElement::Scalar(token),
+        Some(token) => return Err(format!("Unexpected token: {:?}", token)),
+        None => return Err("Expected a JSON value".to_string()),
+    };
+    if lexer.reader_index() != line.len() {
+        return Err(format!("Unexpected content after value: {}", String::from_utf8_lossy(&line[lexer.reader_index()..])));
+    }
+    Ok(element)
+}
+
+/// A JSONL line which failed to parse in lenient mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    pub line: usize, // line number, starting at 1
+    pub byte_offset: usize, // offset of the line in the input
+    pub error: String,
+    pub raw: String,
+}
+
+/// Iterate over non empty lines of a JSONL input, yielding line number (starting at 1), byte offset of the trimmed line and trimmed line
+struct JsonlLines<'a> {
+    input: &'a [u8],
+    offset: usize,
+    line_number: usize,
+}
+
+impl<'a> JsonlLines<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self { input, offset: 0, line_number: 0 }
+    }
+}
+
+impl<'a> Iterator for JsonlLines<'a> {
+    type Item = (usize, usize, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset < self.input.len() {
+            let line_end = self.input[self.offset..].iter().position(|b| *b == b'\n')
+                .map_or(self.input.len(), |i| self.offset + i);
+            let line = &self.input[self.offset..line_end];
+            self.offset = line_end + 1;
+            self.line_number += 1;
+
+            // Trim whitespace and skip empty lines
+            let trimmed = trim_ascii_whitespace(line);
+            if !trimmed.is_empty() {
+                let byte_offset = trimmed.as_ptr() as usize - self.input.as_ptr() as usize;
+                return Some((self.line_number, byte_offset, trimmed));
+            }
+        }
+        None
     }
 }
 
@@ -688,4 +1228,220 @@ mod jsonl_tests {
         // With max_depth 2, nested objects beyond that should be kept as raw strings
         assert_eq!(result.json[0].pointer.value_type, ValueType::Array(2));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_jsonl_layout() {
+        let content = b"{\"id\": 1, \"user\": {\"name\": \"a\"}}\n{\"id\": 2}";
+        let result = JSONParser::parse_jsonl(content, ParseOptions::default()).unwrap();
+        let entries = result.json.iter().map(|e| (e.pointer.pointer.as_str(), e.pointer.depth, e.pointer.position)).collect::<Vec<_>>();
+
+        assert_eq!(entries, vec![
+            ("", 1, 0),
+            ("/0", 2, 1),
+            ("/0/id", 2, 1),
+            ("/0/user", 2, 2),
+            ("/0/user/name", 3, 3),
+            ("/1", 2, 2),
+            ("/1/id", 2, 1),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_jsonl_truncated_line() {
+        let content = b"{\"id\": 1}\n{\"id\": 2, \"user\": {\"name\": \"tr";
+        let result = JSONParser::parse_jsonl(content, ParseOptions::default());
+        assert_eq!(result.err(), Some("Error parsing JSONL at line 2: Unterminated object".to_string()));
+    }
+
+    #[test]
+    fn test_parse_jsonl_lenient() {
+        let content = b"{\"id\": 1}\n{\"id\" 2}\n\n{\"id\": 3}\n{\"id\": 4, \"tr";
+        let (result, errors) = JSONParser::parse_jsonl_lenient(content, ParseOptions::default(), 10).unwrap();
+
+        assert_eq!(result.json[0].pointer.value_type, ValueType::Array(2));
+        assert_eq!(result.json[1].pointer.pointer, "/0");
+        assert_eq!(result.json[2].pointer.pointer, "/0/id");
+        assert_eq!(result.json[3].pointer.pointer, "/1");
+        assert_eq!(result.json[4].pointer.pointer, "/1/id");
+        assert_eq!(result.json[4].value, Some("3".to_string()));
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].line, 2);
+        assert_eq!(errors[0].byte_offset, 10);
+        assert_eq!(errors[0].raw, "{\"id\" 2}");
+        assert_eq!(errors[1].line, 5);
+        assert_eq!(errors[1].byte_offset, 30);
+        assert_eq!(errors[1].error, "Unterminated object");
+        assert_eq!(errors[1].raw, "{\"id\": 4, \"tr");
+    }
+
+    #[test]
+    fn test_parse_jsonl_lenient_error_budget() {
+        let content = b"{\"id\": 1}\n{\"id\" 2}\n{\"id\" 3}\n{\"id\": 4}";
+        assert!(JSONParser::parse_jsonl_lenient(content, ParseOptions::default(), 2).is_ok());
+        let result = JSONParser::parse_jsonl_lenient(content, ParseOptions::default(), 1);
+        assert!(result.err().unwrap().contains("at line 3"));
+    }
+
+    #[test]
+    fn test_is_jsonl_any_value() {
+        assert!(JSONParser::is_jsonl(b"[1, 2]\n[3, 4]"));
+        assert!(JSONParser::is_jsonl(b"\"str\"\n\"other\""));
+        assert!(JSONParser::is_jsonl(b"42\n43\n"));
+        assert!(JSONParser::is_jsonl(b"{\"id\": 1}\n[1]\ntrue"));
+        assert!(!JSONParser::is_jsonl(b"[1, 2,\n 3]"));
+        assert!(!JSONParser::is_jsonl(b"42"));
+        assert!(!JSONParser::is_jsonl(b"42 43"));
+    }
+
+    #[test]
+    fn test_parse_jsonl_any_value() {
+        let content = b"[1,2]\n\"str\"\n42\ntrue\nnull\n{\"id\": 1}";
+        let result = JSONParser::parse_jsonl(content, ParseOptions::default()).unwrap();
+
+        assert_eq!(result.json[0].pointer.value_type, ValueType::Array(6));
+        assert_eq!(result.json[1].pointer.pointer, "/0");
+        assert_eq!(result.json[1].pointer.value_type, ValueType::Array(2));
+        assert_eq!(result.json[1].pointer.depth, 2);
+        assert_eq!(result.json[2].pointer.pointer, "/0/0");
+        assert_eq!(result.json[2].pointer.depth, 2);
+        assert_eq!(result.json[2].value, Some("1".to_string()));
+        assert_eq!(result.json[3].pointer.pointer, "/0/1");
+        assert_eq!(result.json[4].pointer.pointer, "/1");
+        assert_eq!(result.json[4].pointer.value_type, ValueType::String);
+        assert_eq!(result.json[4].value, Some("str".to_string()));
+        assert_eq!(result.json[5].pointer.pointer, "/2");
+        assert_eq!(result.json[5].pointer.value_type, ValueType::Number);
+        assert_eq!(result.json[5].value, Some("42".to_string()));
+        assert_eq!(result.json[6].pointer.value_type, ValueType::Bool);
+        assert_eq!(result.json[7].pointer.value_type, ValueType::Null);
+        assert_eq!(result.json[8].pointer.pointer, "/5");
+        assert!(matches!(result.json[8].pointer.value_type, ValueType::Object(true, 1)));
+
+        let array = JSONParser::parse("[[1,2],\"str\",42,true,null,{\"id\": 1}]", ParseOptions::default()).unwrap();
+        assert_eq!(result.json.len(), array.json.len());
+
+        assert!(JSONParser::parse_jsonl(b"\"unterminated", ParseOptions::default()).is_err());
+        assert!(JSONParser::parse_jsonl(b"[1, 2", ParseOptions::default()).is_err());
+        assert!(JSONParser::parse_jsonl(b"42 43", ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_json_seq() {
+        let content = b"\x1E{\"id\": 1}\n\x1E[1, 2]\n\x1E\"str\"\n\x1E42\n";
+        let result = JSONParser::parse_json_seq(content, ParseOptions::default()).unwrap();
+
+        assert_eq!(result.json[0].pointer.value_type, ValueType::Array(4));
+        assert_eq!(result.json[1].pointer.pointer, "/0");
+        assert_eq!(result.json[2].pointer.pointer, "/0/id");
+        assert_eq!(result.json[3].pointer.pointer, "/1");
+        assert_eq!(result.json[3].pointer.value_type, ValueType::Array(2));
+        assert_eq!(result.json[6].pointer.pointer, "/2");
+        assert_eq!(result.json[6].value, Some("str".to_string()));
+        assert_eq!(result.json[7].pointer.pointer, "/3");
+        assert_eq!(result.json[7].value, Some("42".to_string()));
+
+        let truncated = b"\x1E{\"id\": 1}\n\x1E{\"id\": \n";
+        assert_eq!(JSONParser::parse_json_seq(truncated, ParseOptions::default()).err(),
+                   Some("Error parsing JSON text sequence at record 2: Unterminated object".to_string()));
+        assert!(JSONParser::parse_json_seq(b"{\"id\": 1}", ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_parse_concatenated() {
+        let content = b"{\"id\": 1}{\"id\": 2} [3]\"str\"42 null";
+        let result = JSONParser::parse_concatenated(content, ParseOptions::default()).unwrap();
+
+        assert_eq!(result.json[0].pointer.value_type, ValueType::Array(6));
+        assert_eq!(result.json[1].pointer.pointer, "/0");
+        assert_eq!(result.json[2].pointer.pointer, "/0/id");
+        assert_eq!(result.json[3].pointer.pointer, "/1");
+        assert_eq!(result.json[4].pointer.pointer, "/1/id");
+        assert_eq!(result.json[4].value, Some("2".to_string()));
+        assert_eq!(result.json[5].pointer.pointer, "/2");
+        assert_eq!(result.json[5].pointer.value_type, ValueType::Array(1));
+        assert_eq!(result.json[6].pointer.pointer, "/2/0");
+        assert_eq!(result.json[7].value, Some("str".to_string()));
+        assert_eq!(result.json[8].value, Some("42".to_string()));
+        assert_eq!(result.json[9].pointer.value_type, ValueType::Null);
+
+        assert!(JSONParser::parse_concatenated(b"{\"id\": 1}{\"id\": ", ParseOptions::default()).is_err());
+        assert_eq!(JSONParser::parse_concatenated(b"{\"a\":1}\"abc", ParseOptions::default()).err(),
+                   Some("Error parsing concatenated JSON at value 1: Unterminated string".to_string()));
+        assert_eq!(JSONParser::parse_concatenated(b"{\"a\":1} xx {\"b\":2}", ParseOptions::default()).err(),
+                   Some("Error parsing concatenated JSON at value 1: Unexpected content after value: xx".to_string()));
+        assert_eq!(JSONParser::parse_concatenated(b"{\"a\":1} xx", ParseOptions::default()).err(),
+                   Some("Error parsing concatenated JSON at value 1: Unexpected content after value: xx".to_string()));
+    }
+
+    #[test]
+    fn test_parse_jsonl_ignores_start_parse_at() {
+        let content = b"{\"id\": 1, \"user\": {\"name\": \"a\"}}\n[{\"id\": 2}]";
+        let result = JSONParser::parse_jsonl(content, ParseOptions::default()).unwrap();
+        let started = JSONParser::parse_jsonl(content, ParseOptions::default().start_parse_at("/user".to_string()).start_depth(3)).unwrap();
+
+        let entries = |result: &ParseResult<String>| result.json.iter().map(|e| (e.pointer.pointer.clone(), e.pointer.depth, e.value.clone())).collect::<Vec<_>>();
+        assert_eq!(entries(&started), entries(&result));
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "serde")]
+mod serde_tests {
+    use crate::{FlatJsonValue, JSONParser, ParseOptions, ParseResult, PointerKey, ValueType};
+
+    #[test]
+    fn value_type_round_trip() {
+        for value_type in [ValueType::Array(3), ValueType::Object(true, 2), ValueType::Object(false, 0), ValueType::Number,
+            ValueType::String, ValueType::Bool, ValueType::Null, ValueType::None] {
+            let json = serde_json::to_string(&value_type).unwrap();
+            assert_eq!(serde_json::from_str::<ValueType>(&json).unwrap(), value_type);
+        }
+        assert_eq!(serde_json::to_string(&ValueType::Object(false, 4)).unwrap(), r#"{"Object":[false,4]}"#);
+    }
+
+    #[test]
+    fn flat_json_value_round_trip() {
+        let mut pointer = PointerKey::from_pointer("/a/b".to_string(), ValueType::Object(true, 1), 2, 7);
+        pointer.column_id = 3;
+        let value = FlatJsonValue { pointer, value: Some("{\"c\": 1}".to_string()) };
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#"{"pointer":{"pointer":"/a/b","value_type":{"Object":[true,1]},"depth":2,"position":7,"column_id":3},"value":"{\"c\": 1}"}"#);
+        let decoded: FlatJsonValue<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.pointer.pointer, "/a/b");
+        assert_eq!(decoded.pointer.value_type, ValueType::Object(true, 1));
+        assert_eq!((decoded.pointer.depth, decoded.pointer.position, decoded.pointer.column_id), (2, 7, 3));
+        assert_eq!(decoded.value, value.value);
+    }
+
+    #[test]
+    fn parse_result_round_trip() {
+        let json = std::fs::read_to_string("examples/grafana.json").unwrap();
+        for options in [ParseOptions::default(), ParseOptions::default().max_depth(2).parse_array(false),
+                        ParseOptions::default().start_parse_at("/panels".to_string()).parse_array(false)] {
+            let result = JSONParser::parse(json.as_str(), options).unwrap();
+            let serialized = serde_json::to_string(&result).unwrap();
+            let decoded: ParseResult<String> = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(decoded.json.len(), result.json.len());
+            for (d, e) in decoded.json.iter().zip(result.json.iter()) {
+                assert_eq!(d.pointer.pointer, e.pointer.pointer);
+                assert_eq!(d.pointer.value_type, e.pointer.value_type);
+                assert_eq!(d.pointer.depth, e.pointer.depth);
+                assert_eq!(d.pointer.position, e.pointer.position);
+                assert_eq!(d.value.as_deref(), e.value);
+            }
+            assert_eq!(decoded.max_json_depth, result.max_json_depth);
+            assert_eq!(decoded.parsing_max_depth, result.parsing_max_depth);
+            assert_eq!(decoded.started_parsing_at, result.started_parsing_at);
+            assert_eq!(decoded.started_parsing_at_index_start, result.started_parsing_at_index_start);
+            assert_eq!(decoded.started_parsing_at_index_end, result.started_parsing_at_index_end);
+            assert_eq!(decoded.depth_after_start_at, result.depth_after_start_at);
+        }
+
+        // Values can be borrowed from serialized data when they don't need to be unescaped
+        let result = JSONParser::parse(r#"{"a": {"b": [1, "x"]}}"#, ParseOptions::default().keep_object_raw_data(false)).unwrap();
+        let serialized = serde_json::to_string(&result).unwrap();
+        let borrowed: ParseResult<&str> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(borrowed.json.iter().map(|e| e.value).collect::<Vec<Option<&str>>>(), result.json.iter().map(|e| e.value).collect::<Vec<Option<&str>>>());
+    }
+}
@@ -0,0 +1,213 @@
+use std::ops::Range;
+use std::thread;
+
+use crate::{parse_array_element, ArrayElement, Dialect, FlatJsonValue, JSONParser, JsonlLines, ParseOptions, ParseResult, PointerKey, ValueType};
+use crate::lexer::{Lexer, Token};
+
+impl JSONParser {
+    /// Same as `parse` but when root is an array, its elements are split and parsed on a worker pool.
+    /// Fallback to `parse` when root is not an array, when `start_parse_at` or `prefix` are set or when dialect is not Json.
+    pub fn parse_parallel(input: &str, options: ParseOptions) -> Result<ParseResult<&str>, String> {
+        if options.start_parse_at.is_some() || options.prefix.is_some() || !options.parse_array || options.start_depth != 1 || options.dialect != Dialect::Json {
+            return JSONParser::parse(input, options);
+        }
+        let (span, elements) = match split_root_array(input.as_bytes()) {
+            Some(split) => split,
+            None => return JSONParser::parse(input, options),
+        };
+        let elements_count = elements.len();
+
+        let chunks = parallel_map(elements.into_iter().enumerate().collect(), |chunk: Vec<(usize, (ArrayElement, Range<usize>))>| {
+            let mut values: Vec<FlatJsonValue<&str>> = Vec::with_capacity(chunk.len() * 8);
+            let mut max_depth = 0;
+            for (index, (element, element_span)) in chunk {
+                max_depth = max_depth.max(parse_array_element(index, element, Some(element_span), &options, &mut values)?);
+            }
+            Ok((values, max_depth))
+        })?;
+
+        let mut json: Vec<FlatJsonValue<&str>> = Vec::with_capacity(chunks.iter().map(|(values, _)| values.len()).sum::<usize>() + 1);
+        let mut root = PointerKey::from_pointer(String::new(), ValueType::Array(elements_count), 1, 0);
+        root.span = Some(span);
+        json.push(FlatJsonValue { pointer: root, value: None });
+        let mut max_json_depth = 0;
+        for (values, max_depth) in chunks {
+            max_json_depth = max_json_depth.max(max_depth);
+            json.extend(values);
+        }
+        // Elements are parsed independently, positions are assigned once they are stitched together
+        for (position, entry) in json.iter_mut().enumerate() {
+            entry.pointer.position = position;
+        }
+        Ok(ParseResult {
+            json,
+            max_json_depth,
+            parsing_max_depth: options.max_depth,
+            started_parsing_at: None,
+            started_parsing_at_index_start: 0,
+            started_parsing_at_index_end: 0,
+            parsing_prefix: None,
+            depth_after_start_at: 0,
+            dialect: Dialect::Json,
+            parse_array: options.parse_array,
+            keep_object_raw_data: options.keep_object_raw_data,
+        })
+    }
+
+    /// Same as `parse_jsonl` but lines are parsed on a worker pool.
+    pub fn parse_jsonl_parallel(input: &[u8], options: ParseOptions) -> Result<ParseResult<String>, String> {
+        let lines = JsonlLines::new(input).enumerate()
+            .map(|(row_index, (line_number, _, line))| (row_index, line_number, line))
+            .collect::<Vec<(usize, usize, &[u8])>>();
+        let rows_count = lines.len();
+
+        let chunks = parallel_map(lines, |chunk: Vec<(usize, usize, &[u8])>| {
+            let mut values: Vec<FlatJsonValue<String>> = Vec::with_capacity(chunk.len() * 8);
+            let mut max_depth = 0;
+            for (row_index, line_number, line) in chunk {
+                let row_max_depth = Self::parse_jsonl_row(line, row_index, &options, &mut values)
+                    .map_err(|e| format!("Error parsing JSONL at line {}: {}", line_number, e))?;
+                max_depth = max_depth.max(row_max_depth);
+            }
+            Ok((values, max_depth))
+        })?;
+
+        let mut all_values: Vec<FlatJsonValue<String>> = Vec::with_capacity(chunks.iter().map(|(values, _)| values.len()).sum::<usize>() + 1);
+        all_values.push(FlatJsonValue {
+            pointer: PointerKey::from_pointer(String::new(), ValueType::Array(0), 1, 0),
+            value: None,
+        });
+        let mut max_depth = 0;
+        for (values, chunk_max_depth) in chunks {
+            max_depth = max_depth.max(chunk_max_depth);
+            all_values.extend(values);
+        }
+        Ok(Self::jsonl_result(all_values, rows_count, max_depth, &options))
+    }
+}
+
+/// Split `items` in contiguous chunks, one per available core, and run `f` on each chunk in its own thread.
+/// Results are returned in chunk order, first error (in chunk order) is returned.
+fn parallel_map<T: Send, R: Send, F>(items: Vec<T>, f: F) -> Result<Vec<R>, String>
+    where F: Fn(Vec<T>) -> Result<R, String> + Sync {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = items.len().div_ceil(threads).max(1);
+    let mut chunks: Vec<Vec<T>> = Vec::with_capacity(threads);
+    let mut items = items.into_iter();
+    loop {
+        let chunk = items.by_ref().take(chunk_size).collect::<Vec<T>>();
+        if chunk.is_empty() {
+            break;
+        }
+        chunks.push(chunk);
+    }
+
+    let f = &f;
+    thread::scope(|scope| {
+        let handles = chunks.into_iter()
+            .map(|chunk| scope.spawn(move || f(chunk)))
+            .collect::<Vec<_>>();
+        handles.into_iter()
+            .map(|handle| handle.join().map_err(|_| "Worker thread panicked".to_string())?)
+            .collect::<Result<Vec<R>, String>>()
+    })
+}
+
+// Span of the root array, and its elements with their span
+type SplitArray<'json> = (Range<usize>, Vec<(ArrayElement<'json>, Range<usize>)>);
+
+/// Find root array elements boundaries using lexer skip functions, without parsing elements.
+/// Returns None when root is not an array.
+fn split_root_array(input: &[u8]) -> Option<SplitArray<'_>> {
+    let mut lexer = Lexer::new(input);
+    if !matches!(lexer.next_token(), Some(Token::SquareOpen)) {
+        return None;
+    }
+    let root_start = lexer.token_start();
+    let mut elements = Vec::with_capacity(128);
+    loop {
+        let token = lexer.next_token()?;
+        let start = lexer.token_start();
+        let element = match token {
+            Token::SquareClose if elements.is_empty() => break,
+            Token::CurlyOpen => ArrayElement::Object(lexer.consume_string_until_end_of_object(true)?),
+            Token::SquareOpen => ArrayElement::Array(lexer.consume_string_until_end_of_array(start, false)?),
+            token @ (Token::String(_) | Token::Number(_) | Token::Boolean(_) | Token::Null) => ArrayElement::Scalar(token),
+            _ => return None,
+        };
+        elements.push((element, start..lexer.reader_index()));
+        match lexer.next_token()? {
+            Token::Comma => {}
+            Token::SquareClose => break,
+            _ => return None,
+        }
+    }
+    Some((root_start..lexer.reader_index(), elements))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use crate::{FlatJsonValue, JSONParser, ParseOptions};
+
+    fn assert_same_entries<V: std::fmt::Debug + Clone + AsRef<str> + crate::GetBytes + PartialEq>(actual: &[FlatJsonValue<V>], expected: &[FlatJsonValue<V>]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert_eq!(a.pointer.pointer, e.pointer.pointer);
+            assert_eq!(a.pointer.value_type, e.pointer.value_type);
+            assert_eq!(a.pointer.depth, e.pointer.depth, "{}", a.pointer.pointer);
+            assert_eq!(a.pointer.position, e.pointer.position, "{}", a.pointer.pointer);
+            assert_eq!(a.value, e.value);
+            assert_eq!(a.pointer.span, e.pointer.span, "{}", a.pointer.pointer);
+            assert_eq!(a.pointer.key_span, e.pointer.key_span, "{}", a.pointer.pointer);
+        }
+    }
+
+    #[test]
+    fn parse_parallel_root_array() {
+        let json = r#" [{"id": 1, "tags": ["a", "b"], "nested": {"a": {"b": 1}}}, 12, "str", null, true, [1, [2, 3]], {}, []] "#;
+        for max_depth in 1..4 {
+            for keep_object_raw_data in [true, false] {
+                let options = ParseOptions::default().max_depth(max_depth).keep_object_raw_data(keep_object_raw_data);
+                let expected = JSONParser::parse(json, options.clone()).unwrap();
+                let actual = JSONParser::parse_parallel(json, options).unwrap();
+                assert_same_entries(&actual.json, &expected.json);
+                assert_eq!(actual.max_json_depth, expected.max_json_depth);
+                assert_eq!(actual.entry_at(8), expected.entry_at(8));
+            }
+        }
+    }
+
+    #[test]
+    fn parse_parallel_root_object() {
+        let path = Path::new("examples/grafana.json");
+        let json = fs::read_to_string(path).unwrap();
+        let expected = JSONParser::parse(json.as_str(), ParseOptions::default()).unwrap();
+        let actual = JSONParser::parse_parallel(json.as_str(), ParseOptions::default()).unwrap();
+        assert_same_entries(&actual.json, &expected.json);
+    }
+
+    #[test]
+    fn parse_jsonl_parallel() {
+        let mut content = String::new();
+        for i in 0..1000 {
+            content.push_str(&format!("{{\"id\": {}, \"user\": {{\"name\": \"user{}\"}}}}\n", i, i));
+            if i % 100 == 0 {
+                content.push('\n');
+            }
+        }
+        let expected = JSONParser::parse_jsonl(content.as_bytes(), ParseOptions::default()).unwrap();
+        let actual = JSONParser::parse_jsonl_parallel(content.as_bytes(), ParseOptions::default()).unwrap();
+        assert_same_entries(&actual.json, &expected.json);
+        assert_eq!(actual.json[0].pointer.value_type, crate::ValueType::Array(1000));
+        assert_eq!(actual.json.last().unwrap().pointer.pointer, "/999/user/name");
+    }
+
+    #[test]
+    fn parse_jsonl_parallel_error() {
+        let content = b"{\"id\": 1}\n{\"id\" 2}\n{\"id\": 3}";
+        let result = JSONParser::parse_jsonl_parallel(content, ParseOptions::default());
+        assert_eq!(result.err(), JSONParser::parse_jsonl(content, ParseOptions::default()).err());
+    }
+}
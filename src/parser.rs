@@ -1,5 +1,6 @@
 use std::mem;
-use crate::{concat_string, FlatJsonValue, ParseOptions, ParseResult, PointerFragment, PointerKey, ValueType};
+use std::ops::Range;
+use crate::{concat_string, Dialect, FlatJsonValue, ParseOptions, ParseResult, PointerFragment, PointerKey, ValueType};
 use crate::lexer::{Lexer, Token};
 
 pub struct Parser<'a, 'json> {
@@ -10,15 +11,19 @@ pub struct Parser<'a, 'json> {
     pub start_parse_at_index_end: usize,
     pub max_depth: usize,
     pub depth_after_start_at: u8,
+    key_span: Option<Range<usize>>, // span of the key of the value being parsed
+    leading_comments: Vec<String>, // comments of the value being parsed
+    last_entry: Option<usize>, // entry which trailing comments are attached to
+    prefix_is_root: bool, // raw value of the node at prefix is parsed, an array there is parsed like a root array whatever parse_array is
 }
 
 
 impl<'a, 'json: 'a> Parser<'a, 'json> {
     pub fn new(lexer: &'a mut Lexer<'json>) -> Self {
-        Self { lexer, current_token: None, state_seen_start_parse_at: false, start_parse_at_index_start: 0, start_parse_at_index_end: 0, max_depth: 0, depth_after_start_at: 0 }
+        Self { lexer, current_token: None, state_seen_start_parse_at: false, start_parse_at_index_start: 0, start_parse_at_index_end: 0, max_depth: 0, depth_after_start_at: 0, key_span: None, leading_comments: Vec::new(), last_entry: None, prefix_is_root: false }
     }
     pub fn new_for_change_depth(lexer: &'a mut Lexer<'json>, depth_after_start_at: u8, max_depth: usize) -> Self {
-        Self { lexer, current_token: None, state_seen_start_parse_at: true, start_parse_at_index_start: 0, start_parse_at_index_end: 0, max_depth, depth_after_start_at }
+        Self { lexer, current_token: None, state_seen_start_parse_at: true, start_parse_at_index_start: 0, start_parse_at_index_end: 0, max_depth, depth_after_start_at, key_span: None, leading_comments: Vec::new(), last_entry: None, prefix_is_root: true }
     }
 
     pub fn parse(&mut self, parse_option: &ParseOptions, depth: u8) -> Result<ParseResult<&'json str>, String> {
@@ -32,6 +37,7 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                 let i = 0;
                 // values.push((PointerKey::from_pointer("".to_string(), ValueType::Object, depth, position), None));
                 self.process_object(&mut pointer_fragment, &mut values, depth, i, parse_option, &mut position)?;
+                self.take_trailing_comments_of_document(&mut values, parse_option)?;
                 return Ok(ParseResult {
                     json: values,
                     max_json_depth: self.max_depth,
@@ -41,6 +47,9 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                     started_parsing_at_index_end: self.start_parse_at_index_end,
                     parsing_prefix: parse_option.prefix.clone(),
                     depth_after_start_at: self.depth_after_start_at,
+                    dialect: parse_option.dialect,
+                    parse_array: parse_option.parse_array,
+                    keep_object_raw_data: parse_option.keep_object_raw_data,
                 });
             }
             if matches!(current_token, Token::SquareOpen) {
@@ -49,8 +58,15 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                 let i = 0;
                 let pointer_index = values.len() as isize;
 
+                let span_start = self.lexer.token_start();
                 values.push(FlatJsonValue { pointer: PointerKey::from_pointer("".to_string(), ValueType::Array(0), depth, i), value: None });
+                // comments before root array lead it
+                self.take_comments(&mut values, false);
+                Self::set_leading_comments(&mut values[0], mem::take(&mut self.leading_comments));
+                self.last_entry = Some(0);
                 self.process_array(&mut pointer_fragment, &mut values, depth, i + 1, parse_option, &mut position, pointer_index)?;
+                values[0].pointer.span = Some(span_start..self.lexer.reader_index());
+                self.take_trailing_comments_of_document(&mut values, parse_option)?;
                 return Ok(ParseResult {
                     json: values,
                     max_json_depth: self.max_depth,
@@ -60,6 +76,9 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                     started_parsing_at_index_end: self.start_parse_at_index_end,
                     parsing_prefix: parse_option.prefix.clone(),
                     depth_after_start_at: self.depth_after_start_at,
+                    dialect: parse_option.dialect,
+                    parse_array: parse_option.parse_array,
+                    keep_object_raw_data: parse_option.keep_object_raw_data,
                 });
             }
             Err(format!("Expected json to start with {{ or [ but started with {:?}", current_token))
@@ -75,17 +94,27 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
         }
         self.next_token();
         while let Some(ref token) = self.current_token {
-            match token {
-                Token::String(key) => {
-                    object_elements += 1;
-                    route.push(concat_string!("/", key));
-                }
+            let json5 = parse_option.dialect == Dialect::Json5;
+            let key = match token {
                 Token::CurlyClose => {
-                    // empty object
+                    // empty object, or trailing comma
                     break;
                 }
+                Token::String(key) | Token::Identifier(key) => *key,
+                // Json5 keywords used as unquoted keys
+                Token::Boolean(key) | Token::Number(key) if json5 && key.starts_with(|c: char| c.is_ascii_alphabetic()) => *key,
+                Token::Null if json5 => "null",
                 _ => return Err(format!("Expected object to have a key at this location: {}, previous valid parsed value: {:?}", Self::concat_route(route),
                                         target.last().map(|e| e.pointer.pointer.as_str()).unwrap_or("")))
+            };
+            object_elements += 1;
+            route.push(concat_string!("/", key));
+            self.key_span = Some(self.lexer.token_start()..self.lexer.reader_index());
+            if depth - self.depth_after_start_at <= parse_option.max_depth {
+                self.take_comments(target, false);
+            } else {
+                // comments of raw values stay in them
+                self.lexer.take_comments();
             }
             self.next_token();
             if let Some(ref _token) = self.current_token {
@@ -141,8 +170,9 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
         while let Some(ref token) = self.current_token {
             if matches!(token, Token::SquareClose) {
                 if pointer_index >= 0 {
-                    let PointerKey { pointer, position, depth, .. } = mem::take(&mut target[pointer_index as usize].pointer);
-                    target[pointer_index as usize] = FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::Array(i), depth, position), value: None };
+                    let array = &mut target[pointer_index as usize];
+                    array.pointer.value_type = ValueType::Array(i);
+                    array.value = None;
                 }
                 break;
             }
@@ -158,6 +188,7 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                 }
                 if depth - self.depth_after_start_at <= parse_option.max_depth {
                     route.push("/0".to_string());
+                    self.take_comments(target, false);
                     self.parse_value(route, target, depth, count, parse_option, position)?;
                     route.pop();
                     self.next_token();
@@ -166,8 +197,13 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                             break;
                         }
                         self.next_token();
+                        if parse_option.dialect != Dialect::Json && matches!(self.current_token, Some(Token::SquareClose)) {
+                            // trailing comma
+                            break;
+                        }
                         if let Some(ref _token) = self.current_token {
                             route.push(format!("/{}", i));
+                            self.take_comments(target, false);
                             self.parse_value(route, target, depth, count, parse_option, position)?;
                             route.pop();
                         } else {
@@ -176,17 +212,22 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                         self.next_token();
                         i += 1;
                     }
-                } else if let Some(array_str) = self.lexer.consume_string_until_end_of_array(array_start_index, nested_array) {
+                } else {
+                    let array_str = self.lexer.consume_string_until_end_of_array(array_start_index, nested_array).ok_or_else(|| "Unterminated array".to_string())?;
                     if pointer_index >= 0 {
-                        let PointerKey { pointer, position, depth, .. } = mem::take(&mut target[pointer_index as usize].pointer);
-                        target[pointer_index as usize] = FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::Array(i), depth, position), value: Some(array_str) };
+                        let array = &mut target[pointer_index as usize];
+                        array.pointer.value_type = ValueType::Array(i);
+                        array.value = Some(array_str);
                     }
                     break;
                 }
-            } else if let Some(array_str) = self.lexer.consume_string_until_end_of_array(array_start_index, nested_array) {
+            } else {
+                let array_str = self.lexer.consume_string_until_end_of_array(array_start_index, nested_array).ok_or_else(|| "Unterminated array".to_string())?;
                 if pointer_index >= 0 {
-                    let PointerKey { position, depth, .. } = target[pointer_index as usize].pointer;
-                    target[pointer_index as usize] = FlatJsonValue { pointer: PointerKey::from_pointer(Self::concat_route(route), ValueType::Array(i), depth, position), value: Some(array_str) };
+                    let array = &mut target[pointer_index as usize];
+                    array.pointer.pointer = Self::concat_route(route);
+                    array.pointer.value_type = ValueType::Array(i);
+                    array.value = Some(array_str);
                 }
                 break;
             }
@@ -195,16 +236,21 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
     }
 
     fn parse_value(&mut self, route: &mut PointerFragment, target: &mut Vec<FlatJsonValue<&'json str>>, depth: u8, count: usize, parse_option: &ParseOptions, position: &mut usize) -> Result<(), String> {
+        // array elements have no key
+        let key_span = self.key_span.take();
+        let leading_comments = mem::take(&mut self.leading_comments);
         match self.current_token {
             Some(ref token) => match token {
                 Token::CurlyOpen => {
                     if depth - self.depth_after_start_at <= parse_option.max_depth {
+                        let span_start = self.lexer.token_start();
                         let start = self.lexer.reader_index();
                         if let Some(object_str) = self.lexer.consume_string_until_end_of_object(true) {
                             *position += 1;
                             let mut parsed = true;
                             let pointer = Self::concat_route(route);
-                            if Self::should_push_to_target(parse_option, &pointer) {
+                            let pushed = Self::should_push_to_target(parse_option, &pointer);
+                            if pushed {
                                 if parse_option.keep_object_raw_data || depth - self.depth_after_start_at == parse_option.max_depth {
                                     target.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::Object(depth - self.depth_after_start_at < parse_option.max_depth, 0), depth, *position), value: Some(object_str) });
                                 } else {
@@ -213,12 +259,26 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                             }
                             self.lexer.set_reader_index(start);
                             let object_index = if target.len() > 0 { target.len() - 1 } else { 0 };
+                            if pushed {
+                                Self::set_leading_comments(&mut target[object_index], leading_comments);
+                                self.last_entry = Some(object_index);
+                            }
                             let elements_count = self.process_object(route, target, depth + 1, count, parse_option, position)?;
                             if object_index < target.len() && matches!(target[object_index].pointer.value_type, ValueType::Object(true, _)) {
                                 target[object_index].pointer.value_type = ValueType::Object(true, elements_count);
                             }
+                            if pushed {
+                                if depth - self.depth_after_start_at < parse_option.max_depth {
+                                    self.take_comments(target, true);
+                                } else {
+                                    self.lexer.take_comments();
+                                }
+                                target[object_index].pointer.span = Some(span_start..self.lexer.reader_index());
+                                target[object_index].pointer.key_span = key_span;
+                                self.last_entry = Some(object_index);
+                            }
                         } else {
-                            panic!("We should no go there! we have not found matching closing curly {}", String::from_utf8_lossy(&self.lexer.reader().data()[start..start + 1000]))
+                            return Err("Unterminated object".to_string());
                         }
                     } else {
                         // consuming remaining token
@@ -239,7 +299,18 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                         }
                     }
                     if should_parse_array {
+                        let span_start = self.lexer.token_start();
+                        if pointer_index >= 0 {
+                            Self::set_leading_comments(&mut target[pointer_index as usize], leading_comments);
+                            self.last_entry = Some(pointer_index as usize);
+                        }
                         self.process_array(route, target, depth + 1, count, parse_option, position, pointer_index)?;
+                        if pointer_index >= 0 {
+                            self.take_comments(target, true);
+                            target[pointer_index as usize].pointer.span = Some(span_start..self.lexer.reader_index());
+                            target[pointer_index as usize].pointer.key_span = key_span;
+                            self.last_entry = Some(pointer_index as usize);
+                        }
                     } else {
                         self.lexer.consume_string_until_end_of_array(self.lexer.reader_index() - 1, false);
                     }
@@ -250,7 +321,7 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                         let pointer = Self::concat_route(route);
                         if Self::should_push_to_target(parse_option, &pointer) {
                             *position += 1;
-                            target.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::String, depth, *position), value: Some(value) });
+                            self.push_scalar(target, PointerKey::from_pointer(pointer, ValueType::String, depth, *position), Some(value), key_span, leading_comments);
                         }
                     }
 
@@ -261,7 +332,7 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                         let pointer = Self::concat_route(route);
                         if Self::should_push_to_target(parse_option, &pointer) {
                             *position += 1;
-                            target.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::Number, depth, *position), value: Some(value) });
+                            self.push_scalar(target, PointerKey::from_pointer(pointer, ValueType::Number, depth, *position), Some(value), key_span, leading_comments);
                         }
                     }
                     Ok(())
@@ -271,7 +342,7 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                         let pointer = Self::concat_route(route);
                         if Self::should_push_to_target(parse_option, &pointer) {
                             *position += 1;
-                            target.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::Bool, depth, *position), value: Some(value) });
+                            self.push_scalar(target, PointerKey::from_pointer(pointer, ValueType::Bool, depth, *position), Some(value), key_span, leading_comments);
                         }
                     }
                     Ok(())
@@ -281,7 +352,7 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
                         let pointer = Self::concat_route(route);
                         if Self::should_push_to_target(parse_option, &pointer) {
                             *position += 1;
-                            target.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, ValueType::Null, depth, *position), value: None });
+                            self.push_scalar(target, PointerKey::from_pointer(pointer, ValueType::Null, depth, *position), None, key_span, leading_comments);
                         }
                     }
                     Ok(())
@@ -292,6 +363,45 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
         }
     }
 
+    #[inline]
+    fn push_scalar(&mut self, target: &mut Vec<FlatJsonValue<&'json str>>, mut key: PointerKey, value: Option<&'json str>, key_span: Option<Range<usize>>, leading_comments: Vec<String>) {
+        key.span = Some(self.lexer.token_start()..self.lexer.reader_index());
+        key.key_span = key_span;
+        self.last_entry = Some(target.len());
+        target.push(FlatJsonValue { pointer: key, value });
+        Self::set_leading_comments(target.last_mut().unwrap(), leading_comments);
+    }
+
+    // Comments read before the current token: those on the line of the last entry, or all of them when its container is closed, trail it.
+    // Others lead the next entry.
+    fn take_comments(&mut self, target: &mut [FlatJsonValue<&'json str>], closing: bool) {
+        for (comment, starts_line) in self.lexer.take_comments() {
+            match self.last_entry {
+                Some(index) if closing || !starts_line => target[index].pointer.comments.get_or_insert_with(Box::default).trailing.push(comment.to_string()),
+                _ => self.leading_comments.push(comment.to_string()),
+            }
+        }
+    }
+
+    fn take_trailing_comments_of_document(&mut self, target: &mut [FlatJsonValue<&'json str>], parse_option: &ParseOptions) -> Result<(), String> {
+        if parse_option.dialect != Dialect::Json {
+            // comments after the root value are only read when looking for another token
+            self.next_token();
+            if let Some(token @ Token::Invalid(_)) = self.current_token.as_ref() {
+                return Err(format!("Unexpected token: {:?}", token));
+            }
+            self.take_comments(target, true);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn set_leading_comments(entry: &mut FlatJsonValue<&'json str>, leading_comments: Vec<String>) {
+        if !leading_comments.is_empty() {
+            entry.pointer.comments.get_or_insert_with(Box::default).leading = leading_comments;
+        }
+    }
+
     #[inline]
     fn should_push_to_target(parse_option: &ParseOptions, pointer: &String) -> bool {
         parse_option.start_parse_at.is_none() || parse_option.start_parse_at.is_some() && pointer.starts_with(parse_option.start_parse_at.as_ref().unwrap())
@@ -300,6 +410,7 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
     fn should_parse_array(&mut self, route: &&mut PointerFragment, parse_option: &ParseOptions) -> bool {
         parse_option.parse_array
             || parse_option.start_parse_at.is_none() && route.is_empty()
+            || self.prefix_is_root && route.len() == 1
             // When parse_array is disable, we allow to parse array if we set a pointer from where we start parsing and this pointer is an array itself, otherwise we would not parse anything
             || (parse_option.start_parse_at.is_some() && !self.state_seen_start_parse_at && parse_option.start_parse_at.as_ref().unwrap().eq(&Self::concat_route(route)))
     }
@@ -322,7 +433,7 @@ impl<'a, 'json: 'a> Parser<'a, 'json> {
 mod tests {
     use std::fs;
     use std::path::Path;
-    use crate::{JSONParser, ParseOptions, ValueType};
+    use crate::{Dialect, JSONParser, ParseOptions, ParseResult, ValueType};
 
     #[test]
     fn object() {
@@ -767,10 +878,10 @@ mod tests {
         assert_eq!(vec[0].pointer.value_type, ValueType::Number);
         assert_eq!(vec[1].pointer.pointer, "/skills");
         assert_eq!(vec[1].pointer.value_type, ValueType::Array(2));
-        assert_eq!(vec[2].pointer.pointer, "/skills/1"); // there is a swap remove
+        assert_eq!(vec[2].pointer.pointer, "/skills/0");
         assert_eq!(vec[2].pointer.value_type, ValueType::Object(false, 0));
         assert!(vec[3].value.is_some());
-        assert_eq!(vec[3].pointer.pointer, "/skills/0");
+        assert_eq!(vec[3].pointer.pointer, "/skills/1");
         assert_eq!(vec[3].pointer.value_type, ValueType::Object(false, 0));
         assert!(vec[3].value.is_some());
 
@@ -800,15 +911,15 @@ mod tests {
         assert_eq!(vec[2].pointer.pointer, "/skills/0");
         assert_eq!(vec[2].pointer.value_type, ValueType::Object(true, 3));
         assert!(vec[2].value.is_some());
-        assert_eq!(vec[4].pointer.pointer, "/skills/0/description");
-        assert_eq!(vec[4].pointer.value_type, ValueType::String);
-        assert_eq!(vec[5].pointer.pointer, "/skills/0/id");
-        assert_eq!(vec[5].pointer.value_type, ValueType::Number);
-        assert_eq!(vec[6].pointer.pointer, "/skills/0/name");
-        assert_eq!(vec[6].pointer.value_type, ValueType::String);
-        assert_eq!(vec[3].pointer.pointer, "/skills/1");
-        assert_eq!(vec[3].pointer.value_type, ValueType::Object(true, 6));
-        assert!(vec[3].value.is_some());
+        assert_eq!(vec[3].pointer.pointer, "/skills/0/description");
+        assert_eq!(vec[3].pointer.value_type, ValueType::String);
+        assert_eq!(vec[4].pointer.pointer, "/skills/0/id");
+        assert_eq!(vec[4].pointer.value_type, ValueType::Number);
+        assert_eq!(vec[5].pointer.pointer, "/skills/0/name");
+        assert_eq!(vec[5].pointer.value_type, ValueType::String);
+        assert_eq!(vec[6].pointer.pointer, "/skills/1");
+        assert_eq!(vec[6].pointer.value_type, ValueType::Object(true, 6));
+        assert!(vec[6].value.is_some());
         assert_eq!(vec[7].pointer.pointer, "/skills/1/description");
         assert_eq!(vec[7].pointer.value_type, ValueType::String);
         assert_eq!(vec[8].pointer.pointer, "/skills/1/id");
@@ -1008,4 +1119,260 @@ mod tests {
         vec.iter().for_each(|v| println!("{:?} -> {:?}", v.pointer, v.value));
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn expand_and_collapse_node() {
+        let json = r#"{"panels": [{"id": 1, "targets": [{"expr": "up"}]}, {"id": 2, "gridPos": {"h": 8}}], "title": "dashboard"}"#;
+        let mut res = JSONParser::parse(json, ParseOptions::default().max_depth(2)).unwrap();
+        let vec = &res.json;
+        assert_eq!(vec.len(), 4);
+        assert_eq!(vec[1].pointer.pointer, "/panels/0");
+        assert_eq!(vec[1].pointer.value_type, ValueType::Object(false, 0));
+
+        JSONParser::expand_node(&mut res, "/panels/1", 1).unwrap();
+        let vec = &res.json;
+        assert_eq!(vec.len(), 6);
+        assert_eq!(vec[1].pointer.pointer, "/panels/0");
+        assert_eq!(vec[1].pointer.value_type, ValueType::Object(false, 0));
+        assert_eq!(vec[2].pointer.pointer, "/panels/1");
+        assert_eq!(vec[2].pointer.value_type, ValueType::Object(true, 2));
+        assert_eq!(vec[3].pointer.pointer, "/panels/1/id");
+        assert_eq!(vec[3].pointer.depth, 3);
+        assert_eq!(vec[4].pointer.pointer, "/panels/1/gridPos");
+        assert_eq!(vec[4].pointer.value_type, ValueType::Object(false, 0));
+        assert_eq!(vec[5].pointer.pointer, "/title");
+        assert!(vec.windows(2).all(|w| w[0].pointer.position < w[1].pointer.position));
+
+        JSONParser::expand_node(&mut res, "/panels/0", 3).unwrap();
+        let vec = &res.json;
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec[2].pointer.pointer, "/panels/0/id");
+        assert_eq!(vec[3].pointer.pointer, "/panels/0/targets");
+        assert_eq!(vec[3].pointer.value_type, ValueType::Array(1));
+        assert_eq!(vec[4].pointer.pointer, "/panels/0/targets/0");
+        assert_eq!(vec[5].pointer.pointer, "/panels/0/targets/0/expr");
+        assert_eq!(vec[5].value, Some("up"));
+        assert_eq!(vec[6].pointer.pointer, "/panels/1");
+        assert!(vec.windows(2).all(|w| w[0].pointer.position < w[1].pointer.position));
+
+        JSONParser::collapse_node(&mut res, "/panels/0").unwrap();
+        let vec = &res.json;
+        assert_eq!(vec.len(), 6);
+        assert_eq!(vec[1].pointer.pointer, "/panels/0");
+        assert_eq!(vec[1].pointer.value_type, ValueType::Object(false, 0));
+        assert_eq!(vec[2].pointer.pointer, "/panels/1");
+
+        assert!(JSONParser::expand_node(&mut res, "/title", 1).is_err());
+        assert!(JSONParser::expand_node(&mut res, "/unknown", 1).is_err());
+    }
+
+    #[test]
+    fn expand_node_with_start_parse_at() {
+        let json = r#"{"skills": [{"id": 1, "copyflags": {"plagiarism": true}}, {"id": 2}], "statuses": [1, 2]}"#;
+        let mut res = JSONParser::parse(json, ParseOptions::default().start_parse_at("/skills".to_string()).max_depth(1)).unwrap().to_owned();
+        assert_eq!(res.json.len(), 3);
+        JSONParser::expand_node_owned(&mut res, "/skills/0", 2).unwrap();
+        let vec = &res.json;
+        assert_eq!(vec.len(), 6);
+        assert_eq!(vec[2].pointer.pointer, "/skills/0/id");
+        assert_eq!(vec[3].pointer.pointer, "/skills/0/copyflags");
+        assert_eq!(vec[3].pointer.value_type, ValueType::Object(true, 1));
+        assert_eq!(vec[4].pointer.pointer, "/skills/0/copyflags/plagiarism");
+        assert_eq!(vec[5].pointer.pointer, "/skills/1");
+        assert_eq!(res.started_parsing_at_index_end, 5);
+    }
+
+    #[test]
+    fn expand_and_collapse_array_with_parse_options() {
+        let json = r#"{"list": [[1, 2], {"a": {"b": 1}}], "x": 1}"#;
+        let entries = |res: &ParseResult<&str>| res.json.iter().map(|e| (e.pointer.pointer.clone(), e.value.map(|v| v.to_string()))).collect::<Vec<_>>();
+        for options in [ParseOptions::default(), ParseOptions::default().keep_object_raw_data(false), ParseOptions::default().parse_array(false)] {
+            let mut res = JSONParser::parse(json, options.clone().max_depth(1)).unwrap();
+            let collapsed = entries(&res);
+            JSONParser::expand_node(&mut res, "/list", 2).unwrap();
+            assert_eq!(res.json[0].value, Some(r#"[[1, 2], {"a": {"b": 1}}]"#));
+            assert_eq!(res.json[2].pointer.pointer, if options.parse_array { "/list/0/0" } else { "/list/1" });
+            // Children are parsed with the options of the result
+            assert_eq!(res.json.iter().find(|e| e.pointer.pointer == "/list/1").unwrap().value.is_some(), options.keep_object_raw_data);
+            assert!(res.json.windows(2).all(|w| w[0].pointer.position < w[1].pointer.position));
+
+            JSONParser::collapse_node(&mut res, "/list").unwrap();
+            assert_eq!(entries(&res), collapsed);
+            assert_eq!(res.json[0].pointer.value_type, ValueType::Array(2));
+        }
+    }
+
+    #[test]
+    fn change_depth_keeps_document_order() {
+        let json = r#"{"aaa": 1, "skills": [{"id": 1, "requires": {"spcost": 30}}, {"id": 2, "copyflags": {"plagiarism": true}}], "statuses": {"agi": 10}}"#;
+        let expected = JSONParser::parse(json, ParseOptions::default().start_parse_at("/skills".to_string()).max_depth(3)).unwrap();
+        let mut res = JSONParser::parse(json, ParseOptions::default().start_parse_at("/skills".to_string()).max_depth(1)).unwrap();
+        JSONParser::change_depth(&mut res, ParseOptions::default().start_parse_at("/skills".to_string()).max_depth(2)).unwrap();
+        JSONParser::change_depth(&mut res, ParseOptions::default().start_parse_at("/skills".to_string()).max_depth(3)).unwrap();
+        let pointers = res.json.iter().map(|e| e.pointer.pointer.as_str()).collect::<Vec<&str>>();
+        let expected_pointers = expected.json.iter().map(|e| e.pointer.pointer.as_str()).collect::<Vec<&str>>();
+        assert_eq!(pointers, expected_pointers);
+        assert!(res.json.windows(2).all(|w| w[0].pointer.position < w[1].pointer.position));
+        assert_eq!(res.json[res.started_parsing_at_index_start].pointer.pointer, "/skills");
+        assert_eq!(res.started_parsing_at_index_end, res.json.len() - 1);
+    }
+
+    #[test]
+    fn failing_change_depth_leaves_result_unchanged() {
+        let json = r#"{"a": {"b": 1}, "c": {"d": 2}, "e": {"f": 3}}"#;
+        let mut res = JSONParser::parse(json, ParseOptions::default().max_depth(1)).unwrap();
+        res.json[1].value = Some(r#"{"d" 2}"#);
+        let before = format!("{:?}", res);
+        assert!(JSONParser::change_depth(&mut res, ParseOptions::default().max_depth(2)).is_err());
+        assert_eq!(format!("{:?}", res), before);
+        assert_eq!(res.parsing_max_depth, 1);
+    }
+
+    #[test]
+    fn expand_node_levels_and_errors() {
+        let json = r#"{"a": {"b": {"c": [1]}}, "e": {"f": 3}}"#;
+        let mut res = JSONParser::parse(json, ParseOptions::default().max_depth(1)).unwrap();
+        JSONParser::expand_node(&mut res, "/a", 255).unwrap();
+        assert_eq!(res.json.iter().map(|e| e.pointer.pointer.as_str()).collect::<Vec<&str>>(), vec!["/a", "/a/b", "/a/b/c", "/a/b/c/0", "/e"]);
+
+        res.json[0].value = Some(r#"{"b" 2}"#);
+        let before = format!("{:?}", res);
+        assert!(JSONParser::expand_node(&mut res, "/a", 1).is_err());
+        assert_eq!(format!("{:?}", res), before);
+    }
+
+    #[test]
+    fn value_spans() {
+        let json = r#"{"a": [1, "x", []], "b" : {"c": null, "d": {"e": true}}, "f": -1.5e3}"#;
+        let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let spans = res.json.iter().map(|e| (e.pointer.pointer.as_str(), &json[e.pointer.span.clone().unwrap()])).collect::<Vec<(&str, &str)>>();
+        assert_eq!(spans, vec![("/a", r#"[1, "x", []]"#), ("/a/0", "1"), ("/a/1", r#""x""#), ("/a/2", "[]"),
+                               ("/b", r#"{"c": null, "d": {"e": true}}"#), ("/b/c", "null"), ("/b/d", r#"{"e": true}"#), ("/b/d/e", "true"), ("/f", "-1.5e3")]);
+
+        let json = " [{\"a\": 1}, 2] ";
+        let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        assert_eq!(res.json[0].pointer.span, Some(1..json.len() - 1));
+
+        // Spans of entries parsed from raw values are relative to the whole input
+        let json = r#"{"skills": [{"id": 1, "requires": {"spcost": 30}}], "statuses": {"agi": 10}}"#;
+        let expected = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let mut res = JSONParser::parse(json, ParseOptions::default().max_depth(1)).unwrap();
+        JSONParser::change_depth(&mut res, ParseOptions::default().max_depth(3)).unwrap();
+        JSONParser::expand_node(&mut res, "/skills/0/requires", 1).unwrap();
+        assert_eq!(res.json.len(), expected.json.len());
+        for entry in res.json.iter() {
+            let expected = expected.json.iter().find(|e| e.pointer.pointer == entry.pointer.pointer).unwrap();
+            assert_eq!(entry.pointer.span, expected.pointer.span, "{}", entry.pointer.pointer);
+        }
+    }
+
+    #[test]
+    fn key_spans() {
+        let json = r#"{"a": [1, {"b" : 2}], "c": {"d": "x"}}"#;
+        let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let spans = res.json.iter().map(|e| (e.pointer.pointer.as_str(), e.key_span().map(|s| &json[s]), &json[e.span().unwrap()])).collect::<Vec<(&str, Option<&str>, &str)>>();
+        assert_eq!(spans, vec![("/a", Some(r#""a""#), r#""a": [1, {"b" : 2}]"#), ("/a/0", None, "1"), ("/a/1", None, r#"{"b" : 2}"#),
+                               ("/a/1/b", Some(r#""b""#), r#""b" : 2"#), ("/c", Some(r#""c""#), r#""c": {"d": "x"}"#), ("/c/d", Some(r#""d""#), r#""d": "x""#)]);
+
+        let pointer_at = |offset: usize| res.entry_at(offset).map(|i| res.json[i].pointer.pointer.as_str());
+        assert_eq!(pointer_at(json.find('a').unwrap()), Some("/a"));
+        assert_eq!(pointer_at(json.find('2').unwrap()), Some("/a/1/b"));
+        assert_eq!(pointer_at(json.find(", \"c\"").unwrap()), None);
+        assert_eq!(pointer_at(json.find("\"x\"").unwrap()), Some("/c/d"));
+
+        // Key spans of entries parsed from raw values are relative to the whole input
+        let mut res = JSONParser::parse(json, ParseOptions::default().max_depth(1)).unwrap();
+        JSONParser::change_depth(&mut res, ParseOptions::default().max_depth(3)).unwrap();
+        let d = res.json.iter().find(|e| e.pointer.pointer == "/c/d").unwrap();
+        assert_eq!(&json[d.key_span().unwrap()], r#""d""#);
+    }
+
+    #[test]
+    fn jsonc() {
+        let json = r#"{
+            // "a": null, } ]
+            "a": [1, /* ] " */ 2,],
+            "b": {"c": "http://x/*y*/", /* "d": 1 */},
+        }"#;
+        let res = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Jsonc)).unwrap();
+        let entries = res.json.iter().map(|e| (e.pointer.pointer.as_str(), e.pointer.value_type, e.value)).collect::<Vec<_>>();
+        assert_eq!(entries, vec![("/a", ValueType::Array(2), None), ("/a/0", ValueType::Number, Some("1")), ("/a/1", ValueType::Number, Some("2")),
+                                 ("/b", ValueType::Object(true, 1), Some(r#"{"c": "http://x/*y*/", /* "d": 1 */}"#)), ("/b/c", ValueType::String, Some("http://x/*y*/"))]);
+
+        // Raw values skipped over are parsed again with the same dialect
+        let mut res = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Jsonc).max_depth(1)).unwrap();
+        JSONParser::change_depth(&mut res, ParseOptions::default().max_depth(2)).unwrap();
+        assert_eq!(res.json.iter().map(|e| e.pointer.pointer.as_str()).collect::<Vec<_>>(), vec!["/a", "/a/0", "/a/1", "/b", "/b/c"]);
+
+        // Unexpected bytes are errors instead of being skipped
+        assert!(JSONParser::parse(r#"{"a": 1 # comment
+        }"#, ParseOptions::default().dialect(Dialect::Jsonc)).is_err());
+        assert!(JSONParser::parse("[1, 2,]", ParseOptions::default()).is_err());
+        // Unclosed block comments too
+        assert!(JSONParser::parse(r#"{"a": 1} /* comment"#, ParseOptions::default().dialect(Dialect::Jsonc)).is_err());
+        assert!(JSONParser::parse(r#"{"a": /*/ 1}"#, ParseOptions::default().dialect(Dialect::Jsonc)).is_err());
+        assert!(JSONParser::parse("[1, /* 2]", ParseOptions::default().dialect(Dialect::Json5)).is_err());
+        assert!(JSONParser::parse(r#"{"a": {"b": 1 /* }}"#, ParseOptions::default().dialect(Dialect::Jsonc).max_depth(1)).is_err());
+        assert!(JSONParser::parse(r#"{"a": [1 /* ]}"#, ParseOptions::default().dialect(Dialect::Jsonc).max_depth(1)).is_err());
+    }
+
+    #[test]
+    fn json5() {
+        let json = r#"{unquoted: 'it\'s "quoted"', $x_1: 0x1F, true: +1, null: .5, nan: NaN, inf: -Infinity, nullable: 'a',}"#;
+        let res = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Json5)).unwrap();
+        let entries = res.json.iter().map(|e| (e.pointer.pointer.as_str(), e.pointer.value_type, e.value.unwrap())).collect::<Vec<_>>();
+        assert_eq!(entries, vec![("/unquoted", ValueType::String, r#"it\'s "quoted""#), ("/$x_1", ValueType::Number, "0x1F"), ("/true", ValueType::Number, "+1"),
+                                 ("/null", ValueType::Number, ".5"), ("/nan", ValueType::Number, "NaN"), ("/inf", ValueType::Number, "-Infinity"), ("/nullable", ValueType::String, "a")]);
+        assert_eq!(&json[res.json[0].key_span().unwrap()], "unquoted");
+
+        let mut json = res.json.clone();
+        let value = JSONParser::serialize(&mut json).unwrap();
+        assert!(matches!(value, crate::serializer::Value::Object(ref obj) if matches!(obj.get("$x_1"), Some(crate::serializer::Value::Integer(31)))));
+
+        let json = "{a: {b: '}', // }\n c: [']', /* ] */]}}";
+        let res = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Json5).max_depth(1)).unwrap();
+        assert_eq!(res.json[0].value, Some("{b: '}', // }\n c: [']', /* ] */]}"));
+
+        // Identifiers are only keys
+        assert!(JSONParser::parse("{a: b}", ParseOptions::default().dialect(Dialect::Json5)).is_err());
+        assert!(JSONParser::parse("{a: 1}", ParseOptions::default().dialect(Dialect::Jsonc)).is_err());
+    }
+
+    #[test]
+    fn comments() {
+        let json = r#"// settings
+{
+    // leading of a
+    "a": 1, // trailing of a
+    "b": { /* trailing of b */
+        "c": [true, /* trailing of 0 */
+            // leading of 1
+            false
+            // end of c
+        ]
+    },
+    "d": {"e": {"f": {"g": 1 /* raw */}}} // trailing of d
+    // end of document
+}
+// after document"#;
+        let res = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Jsonc).max_depth(3)).unwrap();
+        let comments = res.json.iter().map(|e| (e.pointer.pointer.as_str(), e.pointer.comments.as_deref().map(|c| (c.leading.clone(), c.trailing.clone()))))
+            .collect::<Vec<_>>();
+        let strings = |comments: &[&str]| comments.iter().map(|c| c.to_string()).collect::<Vec<String>>();
+        assert_eq!(comments, vec![
+            ("/a", Some((strings(&["// settings", "// leading of a"]), strings(&["// trailing of a"])))),
+            ("/b", Some((vec![], strings(&["/* trailing of b */"])))),
+            ("/b/c", None),
+            ("/b/c/0", Some((vec![], strings(&["/* trailing of 0 */"])))),
+            ("/b/c/1", Some((strings(&["// leading of 1"]), strings(&["// end of c"])))),
+            ("/d", Some((vec![], strings(&["// trailing of d", "// end of document", "// after document"])))),
+            ("/d/e", None),
+            ("/d/e/f", None),
+        ]);
+        // comments of raw values stay in them
+        assert_eq!(res.json.last().unwrap().value, Some(r#"{"g": 1 /* raw */}"#));
+        let mut res = res;
+        JSONParser::change_depth(&mut res, ParseOptions::default().max_depth(4)).unwrap();
+        assert_eq!(res.json.last().unwrap().pointer.comments.as_ref().unwrap().trailing, strings(&["/* raw */"]));
+    }
+}
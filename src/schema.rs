@@ -0,0 +1,343 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Debug;
+
+use crate::{concat_string, FlatJsonValue, GetBytes, ParseResult, PointerKey, ValueType};
+use crate::serializer::{serialize_to_json, Value};
+use crate::strings::json5_number_to_json;
+
+/// Strings of a node are emitted as an enum when there are at most this number of distinct values
+pub const STRING_ENUM_MAX_CARDINALITY: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchemaType {
+    Null,
+    Boolean,
+    Integer,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl SchemaType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaType::Null => "null",
+            SchemaType::Boolean => "boolean",
+            SchemaType::Integer => "integer",
+            SchemaType::Number => "number",
+            SchemaType::String => "string",
+            SchemaType::Array => "array",
+            SchemaType::Object => "object",
+        }
+    }
+}
+
+/// Merged types of all values found at a normalised pointer
+#[derive(Debug, Clone, Default)]
+pub struct SchemaNode {
+    pub types: BTreeSet<SchemaType>,
+    pub occurrences: usize,
+    pub parsed_objects: usize, // number of object values whose keys have been parsed
+    pub properties: Vec<(String, SchemaNode)>,
+    pub items: Option<Box<SchemaNode>>,
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+    pub string_values: Option<BTreeSet<String>>, // None once there are more than STRING_ENUM_MAX_CARDINALITY distinct values
+    pub strings: usize,
+}
+
+impl SchemaNode {
+    pub fn property(&self, key: &str) -> Option<&SchemaNode> {
+        self.properties.iter().find(|(k, _)| k.eq(key)).map(|(_, node)| node)
+    }
+
+    /// Keys present in every parsed object
+    pub fn required(&self) -> Vec<&str> {
+        if self.parsed_objects == 0 {
+            return vec![];
+        }
+        self.properties.iter()
+            .filter(|(_, node)| node.occurrences >= self.parsed_objects)
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    pub fn is_nullable(&self) -> bool {
+        self.types.contains(&SchemaType::Null)
+    }
+
+    /// Distinct string values when node only contains repeated strings below cardinality threshold
+    pub fn string_enum(&self) -> Option<Vec<&str>> {
+        let values = self.string_values.as_ref()?;
+        if self.types.len() != 1 || values.len() >= self.strings {
+            return None;
+        }
+        Some(values.iter().map(|v| v.as_str()).collect())
+    }
+
+    fn items_node(&mut self) -> &mut SchemaNode {
+        self.items.get_or_insert_with(Default::default)
+    }
+
+    fn child(&mut self, segment: &str) -> &mut SchemaNode {
+        let index = match self.properties.iter().position(|(k, _)| k.eq(segment)) {
+            Some(index) => index,
+            None => {
+                self.properties.push((segment.to_string(), SchemaNode::default()));
+                self.properties.len() - 1
+            }
+        };
+        &mut self.properties[index].1
+    }
+
+    fn add<V: Debug + Clone + AsRef<str> + GetBytes>(&mut self, entry: &FlatJsonValue<V>) {
+        self.occurrences += 1;
+        match entry.pointer.value_type {
+            ValueType::Object(parsed, _) => {
+                self.types.insert(SchemaType::Object);
+                if parsed {
+                    self.parsed_objects += 1;
+                }
+            }
+            ValueType::Array(_) => {
+                self.types.insert(SchemaType::Array);
+            }
+            ValueType::Number => {
+                // Json5 numbers are normalised first, e.g 0x1E is an integer, NaN a number without bounds
+                let number = json5_number_to_json(entry.value.as_ref().map_or("", |v| v.as_ref()));
+                let is_integer = number.as_ref().is_some_and(|n| !n.contains(['.', 'e', 'E']));
+                self.types.insert(if is_integer { SchemaType::Integer } else { SchemaType::Number });
+                // Numbers out of f64 range (e.g 1e400) have no bound which can be written
+                if let Some(n) = number.and_then(|n| n.parse::<f64>().ok()).filter(|n| n.is_finite()) {
+                    self.minimum = Some(self.minimum.map_or(n, |m| m.min(n)));
+                    self.maximum = Some(self.maximum.map_or(n, |m| m.max(n)));
+                }
+            }
+            ValueType::String => {
+                self.types.insert(SchemaType::String);
+                if self.strings == 0 {
+                    self.string_values = Some(BTreeSet::new());
+                }
+                self.strings += 1;
+                if let Some(values) = self.string_values.as_mut() {
+                    values.insert(entry.value.as_ref().map_or("", |v| v.as_ref()).to_string());
+                    if values.len() > STRING_ENUM_MAX_CARDINALITY {
+                        self.string_values = None;
+                    }
+                }
+            }
+            ValueType::Bool => {
+                self.types.insert(SchemaType::Boolean);
+            }
+            ValueType::Null | ValueType::None => {
+                self.types.insert(SchemaType::Null);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    pub root: SchemaNode,
+}
+
+/// Infer schema of `result` by merging entries per normalised pointer, array indexes being replaced by `*`, e.g: /items/*/name.
+/// Nested objects and arrays which have not been parsed (because of max depth or `parse_array(false)`) only contribute their type.
+pub fn infer_schema<V: Debug + Clone + AsRef<str> + GetBytes>(result: &ParseResult<V>) -> Schema {
+    let mut schema = Schema::default();
+    // Path of container entries in the schema, array elements being None, and whether they are arrays.
+    // Items are not routed through a `*` segment, which could be a key as well.
+    let mut containers: HashMap<&str, (Vec<Option<&str>>, bool)> = HashMap::new();
+    for entry in result.json.iter() {
+        let pointer = entry.pointer.pointer.as_str();
+        if pointer.is_empty() {
+            schema.root.add(entry);
+            containers.insert(pointer, (Vec::new(), matches!(entry.pointer.value_type, ValueType::Array(_))));
+            continue;
+        }
+        if schema.root.occurrences == 0 {
+            // Root object has no entry
+            schema.root.occurrences = 1;
+            schema.root.parsed_objects = 1;
+            schema.root.types.insert(SchemaType::Object);
+        }
+        let slash = pointer.rfind('/').unwrap_or(0);
+        let (parent, key) = (&pointer[..slash], &pointer[slash + 1..]);
+        let path = match containers.get(parent) {
+            Some((parent_path, is_array)) => {
+                let mut path = parent_path.clone();
+                path.push(if *is_array { None } else { Some(key) });
+                path
+            }
+            None => pointer.split('/').skip(1).map(Some).collect(),
+        };
+        let mut node = &mut schema.root;
+        for segment in path.iter() {
+            node = match segment {
+                Some(key) => node.child(key),
+                None => node.items_node(),
+            };
+        }
+        node.add(entry);
+        if matches!(entry.pointer.value_type, ValueType::Object(..) | ValueType::Array(_)) {
+            containers.insert(pointer, (path, matches!(entry.pointer.value_type, ValueType::Array(_))));
+        }
+    }
+    schema
+}
+
+impl Schema {
+    /// Node at normalised `pointer`, e.g: /items/*/name. `*` is a property named `*` of nodes without items.
+    pub fn node(&self, pointer: &str) -> Option<&SchemaNode> {
+        let mut node = &self.root;
+        for segment in pointer.split('/').skip(1) {
+            node = match node.items.as_deref() {
+                Some(items) if segment == "*" => items,
+                _ => node.property(segment)?,
+            };
+        }
+        Some(node)
+    }
+
+    /// JSON Schema draft 2020-12 document as flat entries
+    pub fn to_flat_json_schema(&self) -> Vec<FlatJsonValue<String>> {
+        let mut entries: Vec<FlatJsonValue<String>> = Vec::with_capacity(64);
+        push_entry(&mut entries, "/$schema".to_string(), ValueType::String, Some("https://json-schema.org/draft/2020-12/schema".to_string()));
+        push_node(&mut entries, "", &self.root);
+        entries
+    }
+
+    /// JSON Schema draft 2020-12 document
+    pub fn to_json_schema(&self) -> Result<Value<String>, String> {
+        serialize_to_json(&mut self.to_flat_json_schema())
+    }
+}
+
+fn push_entry(entries: &mut Vec<FlatJsonValue<String>>, pointer: String, value_type: ValueType, value: Option<String>) {
+    let depth = pointer.matches('/').count() as u8;
+    let position = entries.len();
+    entries.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, value_type, depth, position), value });
+}
+
+fn push_strings<'a>(entries: &mut Vec<FlatJsonValue<String>>, pointer: String, values: impl ExactSizeIterator<Item=&'a str>) {
+    push_entry(entries, pointer.clone(), ValueType::Array(values.len()), None);
+    for (i, value) in values.enumerate() {
+        push_entry(entries, concat_string!(pointer, "/", i.to_string()), ValueType::String, Some(value.to_string()));
+    }
+}
+
+fn push_node(entries: &mut Vec<FlatJsonValue<String>>, pointer: &str, node: &SchemaNode) {
+    let mut types = node.types.iter().map(|t| t.name()).collect::<Vec<&str>>();
+    if node.types.contains(&SchemaType::Number) {
+        // integer is a subset of number
+        types.retain(|t| *t != "integer");
+    }
+    match types.len() {
+        0 => {}
+        1 => push_entry(entries, concat_string!(pointer, "/type"), ValueType::String, Some(types[0].to_string())),
+        _ => push_strings(entries, concat_string!(pointer, "/type"), types.into_iter()),
+    }
+    if let Some(values) = node.string_enum() {
+        push_strings(entries, concat_string!(pointer, "/enum"), values.into_iter());
+    }
+    if let Some(minimum) = node.minimum {
+        push_entry(entries, concat_string!(pointer, "/minimum"), ValueType::Number, Some(minimum.to_string()));
+    }
+    if let Some(maximum) = node.maximum {
+        push_entry(entries, concat_string!(pointer, "/maximum"), ValueType::Number, Some(maximum.to_string()));
+    }
+    if !node.properties.is_empty() {
+        let properties_pointer = concat_string!(pointer, "/properties");
+        push_entry(entries, properties_pointer.clone(), ValueType::Object(true, node.properties.len()), None);
+        for (key, property) in node.properties.iter() {
+            let property_pointer = concat_string!(properties_pointer, "/", key);
+            push_entry(entries, property_pointer.clone(), ValueType::Object(true, 0), None);
+            push_node(entries, &property_pointer, property);
+        }
+        let required = node.required();
+        if !required.is_empty() {
+            push_strings(entries, concat_string!(pointer, "/required"), required.into_iter());
+        }
+    }
+    if let Some(items) = node.items.as_ref() {
+        let items_pointer = concat_string!(pointer, "/items");
+        push_entry(entries, items_pointer.clone(), ValueType::Object(true, 0), None);
+        push_node(entries, &items_pointer, items);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dialect, JSONParser, ParseOptions};
+    use crate::schema::{infer_schema, SchemaType};
+
+    #[test]
+    fn infer_schema_merges_array_elements() {
+        let json = r#"{"items": [{"id": 1, "kind": "a", "score": 1.5, "tags": ["x"]}, {"id": 20, "kind": "b", "score": null}, {"id": 3, "kind": "a", "extra": {"k": true}}], "next": null}"#;
+        let result = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let schema = infer_schema(&result);
+
+        assert_eq!(schema.root.required(), vec!["items", "next"]);
+        let item = schema.node("/items/*").unwrap();
+        assert_eq!(item.occurrences, 3);
+        assert_eq!(item.required(), vec!["id", "kind"]);
+        let id = schema.node("/items/*/id").unwrap();
+        assert_eq!(id.types.iter().copied().collect::<Vec<SchemaType>>(), vec![SchemaType::Integer]);
+        assert_eq!((id.minimum, id.maximum), (Some(1.0), Some(20.0)));
+        assert_eq!(schema.node("/items/*/kind").unwrap().string_enum(), Some(vec!["a", "b"]));
+        assert!(schema.node("/items/*/score").unwrap().is_nullable());
+        assert_eq!(schema.node("/items/*/tags/*").unwrap().string_enum(), None);
+        assert!(schema.node("/items/*/extra/k").is_some());
+        assert!(schema.node("/items/0").is_none());
+    }
+
+    #[test]
+    fn infer_schema_unparsed_values() {
+        let json = r#"[{"a": {"b": 1}, "c": [1, 2]}, {"a": {"b": 2}, "c": [3]}]"#;
+        let result = JSONParser::parse(json, ParseOptions::default().max_depth(2).parse_array(false)).unwrap();
+        let schema = infer_schema(&result);
+        assert_eq!(schema.root.types.iter().copied().collect::<Vec<SchemaType>>(), vec![SchemaType::Array]);
+        let a = schema.node("/*/a").unwrap();
+        assert_eq!(a.types.iter().copied().collect::<Vec<SchemaType>>(), vec![SchemaType::Object]);
+        assert!(a.properties.is_empty());
+        assert_eq!(schema.node("/*").unwrap().required(), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn infer_schema_star_keys_and_huge_numbers() {
+        let json = r#"[{"*": 1, "n": 1e400}, {"*": 2, "n": 3}]"#;
+        let result = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let schema = infer_schema(&result);
+        let item = schema.root.items.as_deref().unwrap();
+        assert!(item.items.is_none());
+        assert_eq!(item.required(), vec!["*", "n"]);
+        assert_eq!(schema.node("/*/*").unwrap().maximum, Some(2.0));
+        let n = schema.node("/*/n").unwrap();
+        assert_eq!((n.minimum, n.maximum), (Some(3.0), Some(3.0)));
+        assert!(!schema.to_json_schema().unwrap().to_json().contains("inf"));
+    }
+
+    #[test]
+    fn infer_schema_json5_numbers() {
+        let result = JSONParser::parse("{hex: 0x1E, dot: .5, nan: NaN}", ParseOptions::default().dialect(Dialect::Json5)).unwrap();
+        let schema = infer_schema(&result);
+        let hex = schema.node("/hex").unwrap();
+        assert_eq!(hex.types.iter().copied().collect::<Vec<SchemaType>>(), vec![SchemaType::Integer]);
+        assert_eq!((hex.minimum, hex.maximum), (Some(30.0), Some(30.0)));
+        assert_eq!(schema.node("/dot").unwrap().types.iter().copied().collect::<Vec<SchemaType>>(), vec![SchemaType::Number]);
+        let nan = schema.node("/nan").unwrap();
+        assert_eq!((nan.types.iter().copied().collect::<Vec<SchemaType>>(), nan.minimum), (vec![SchemaType::Number], None));
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn infer_schema_to_json_schema() {
+        let jsonl = b"{\"id\": 1, \"status\": \"on\", \"score\": 2.5}\n{\"id\": 2, \"status\": \"on\", \"score\": null}\n";
+        let result = JSONParser::parse_jsonl(jsonl, ParseOptions::default()).unwrap();
+        let json_schema = infer_schema(&result).to_json_schema().unwrap().to_json_compact();
+        assert_eq!(json_schema, concat!(r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","type":"array","items":{"type":"object","#,
+            r#""properties":{"id":{"type":"integer","minimum":1,"maximum":2},"status":{"type":"string","enum":["on"]},"#,
+            r#""score":{"type":["null","number"],"minimum":2.5,"maximum":2.5}},"required":["id","status","score"]}}"#));
+    }
+}
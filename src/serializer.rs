@@ -1,15 +1,21 @@
-use std::cmp::Ordering;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::io;
+use std::io::Write;
+use std::mem;
+use std::ops::Range;
 
 use std::str::FromStr;
-use crate::{FlatJsonValue, GetBytes, ValueType};
+use crate::{concat_string, parse_row, split_row, FlatJsonValue, GetBytes, ParseOptions, ParseResult, PointerKey, ValueType};
+use crate::strings::{json5_number_to_json, json5_string_to_json};
 
 #[cfg(feature = "indexmap")]
 type Map<K, V> = indexmap::IndexMap<K, V>;
 #[cfg(not(feature = "indexmap"))]
 type Map<K, V> = std::collections::HashMap<K, V>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Value<V> {
     Object(Map<String, Value<V>>),
     ObjectSerialized(V),
@@ -17,6 +23,8 @@ pub enum Value<V> {
     ArraySerialized(V),
     Number(f64),
     Integer(i64),
+    /// Number text as found in the document, written as is
+    RawNumber(V),
     String(V),
     Bool(bool),
     Null,
@@ -41,234 +49,217 @@ macro_rules! vec_matches {
     }
 }
 
-pub fn serialize_to_json<'a, V: Debug + Clone + AsRef<str> + GetBytes>(data: &mut Vec<FlatJsonValue<V>>) -> Value<V> {
-    _serialize_to_json(data, 1)
+/// How holes left in arrays by missing indexes are serialized, e.g when some elements have been filtered out
+#[derive(Debug, Clone)]
+pub enum SparseArrays<V> {
+    /// Each missing index is filled with this value
+    Fill(Value<V>),
+    /// Missing indexes are removed, following elements are shifted
+    Compact,
 }
 
-pub fn serialize_to_json_with_option<'a, V: Debug + Clone + AsRef<str> + GetBytes>(data: &mut Vec<FlatJsonValue<V>>, root_depth: u8) -> Value<V> {
-    _serialize_to_json(data, root_depth)
+#[derive(Debug, Clone)]
+pub struct SerializeOptions<V> {
+    pub root_depth: u8,
+    pub sparse_arrays: SparseArrays<V>,
+    /// Keep numbers as `Value::RawNumber` instead of parsing them as i64 or f64, so they are written back exactly as parsed
+    pub raw_numbers: bool,
+    /// Build an array root without a "" array entry, e.g when `root_depth` re-roots entries of an array
+    pub root_is_array: bool,
 }
 
-pub fn _serialize_to_json<'a, V: Debug + Clone + AsRef<str> + GetBytes>(data: &mut Vec<FlatJsonValue<V>>, root_depth: u8) -> Value<V> {
-    let mut root = Value::Object(new_map::<V>(10));
-    let mut root_array = Value::Array(Vec::with_capacity(128));
+impl<V> Default for SerializeOptions<V> {
+    fn default() -> Self {
+        Self {
+            root_depth: 1,
+            sparse_arrays: SparseArrays::Fill(Value::Null),
+            raw_numbers: false,
+            root_is_array: false,
+        }
+    }
+}
+
+impl<V> SerializeOptions<V> {
+    pub fn root_depth(mut self, root_depth: u8) -> Self {
+        self.root_depth = root_depth;
+        self
+    }
+    pub fn sparse_arrays(mut self, sparse_arrays: SparseArrays<V>) -> Self {
+        self.sparse_arrays = sparse_arrays;
+        self
+    }
+    pub fn raw_numbers(mut self, raw_numbers: bool) -> Self {
+        self.raw_numbers = raw_numbers;
+        self
+    }
+    pub fn root_is_array(mut self, root_is_array: bool) -> Self {
+        self.root_is_array = root_is_array;
+        self
+    }
+}
+
+pub fn serialize_to_json<V: Debug + Clone + AsRef<str> + GetBytes>(data: &mut Vec<FlatJsonValue<V>>) -> Result<Value<V>, String> {
+    _serialize_to_json(data, &SerializeOptions::default())
+}
+
+pub fn serialize_to_json_with_option<V: Debug + Clone + AsRef<str> + GetBytes>(data: &mut Vec<FlatJsonValue<V>>, root_depth: u8) -> Result<Value<V>, String> {
+    _serialize_to_json(data, &SerializeOptions::default().root_depth(root_depth))
+}
+
+pub fn serialize_to_json_with_options<V: Debug + Clone + AsRef<str> + GetBytes>(data: &mut Vec<FlatJsonValue<V>>, options: &SerializeOptions<V>) -> Result<Value<V>, String> {
+    _serialize_to_json(data, options)
+}
 
-    let mut root_is_obj = true;
+/// Same as `serialize_to_json` but `data` is left untouched, values of the returned JSON borrow from it.
+pub fn serialize_slice<V: Debug + Clone + AsRef<str> + GetBytes>(data: &[FlatJsonValue<V>]) -> Result<Value<&str>, String> {
+    serialize_slice_with_options(data, &SerializeOptions::default())
+}
 
-    let sorted_data = data;
-    sorted_data.sort_unstable_by(|a, b|
-    // deepest values will go first, because we will iterate in reverse order from the array to pop value
-    match b.pointer.depth.cmp(&a.pointer.depth) {
-        Ordering::Equal => b.pointer.position.cmp(&a.pointer.position),
-        cmp => cmp,
+pub fn serialize_slice_with_options<'a, V: Debug + Clone + AsRef<str> + GetBytes>(data: &'a [FlatJsonValue<V>], options: &SerializeOptions<&'a str>) -> Result<Value<&'a str>, String> {
+    let mut placed: Vec<Placed<&'a str>> = Vec::with_capacity(data.len());
+    for entry in data.iter() {
+        placed.push(Placed { key: &entry.pointer, value: entry.value.as_ref().map(|v| v.as_ref()) });
     }
-    );
+    build(placed, options)
+}
+
+// Value being built, array elements are kept by index until all entries are placed
+enum Node<V> {
+    Leaf(Value<V>),
+    Object(Map<String, Node<V>>),
+    Array(BTreeMap<usize, Node<V>>),
+}
+
+struct Placed<'k, V> {
+    key: &'k PointerKey,
+    value: Option<V>,
+}
 
-    let mut current_parent = &mut root;
-    let mut previous_parent_pointer: Vec<String> = Vec::with_capacity(10);
-    for _i in 0..sorted_data.len() {
-        let entry = sorted_data.pop().unwrap();
-        let key = entry.pointer;
-        let value = entry.value;
+/// Build a JSON value from flat entries, `data` is drained, use `serialize_slice` to keep it.
+/// Pointer segments above `root_depth` are ignored. Missing parents are created from the pointer shape: an array when the following segment is an index, an object otherwise.
+/// The root is an array only when there is a "" array entry or `root_is_array` is set, e.g a root object with keys "0", "1" stays an object.
+pub fn _serialize_to_json<V: Debug + Clone + AsRef<str> + GetBytes>(data: &mut Vec<FlatJsonValue<V>>, options: &SerializeOptions<V>) -> Result<Value<V>, String> {
+    let mut entries = std::mem::take(data);
+    let mut placed: Vec<Placed<V>> = Vec::with_capacity(entries.len());
+    for entry in entries.iter_mut() {
+        placed.push(Placed { key: &entry.pointer, value: entry.value.take() });
+    }
+    build(placed, options)
+}
 
-        if key.pointer.is_empty() && matches!(key.value_type, ValueType::Array(_)) {
-            root_is_obj = false;
-            current_parent = &mut root_array;
+fn build<V: Debug + Clone + AsRef<str> + GetBytes>(entries: Vec<Placed<V>>, options: &SerializeOptions<V>) -> Result<Value<V>, String> {
+    let start = options.root_depth.max(1) as usize - 1;
+    let mut root_is_array = options.root_is_array;
+    // Entries are indexed by their segments, input order is kept as is
+    let mut indexed: Vec<(Vec<&str>, Placed<V>)> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        if entry.key.pointer.is_empty() {
+            if matches!(entry.key.value_type, ValueType::Array(_)) {
+                root_is_array = true;
+            }
             continue;
         }
+        if entry.key.depth < options.root_depth {
+            continue;
+        }
+        let segments: Vec<&str> = entry.key.pointer.split('/').skip(1 + start).collect();
+        if segments.is_empty() {
+            continue;
+        }
+        indexed.push((segments, entry));
+    }
+    // Parents are placed before their children, siblings in document order
+    indexed.sort_by(|(a, a_entry), (b, b_entry)| a.len().cmp(&b.len()).then(a_entry.key.position.cmp(&b_entry.key.position)));
 
-        if key.depth == root_depth {
-            match current_parent {
-                Value::Object(obj) => {
-                    let pointer = if root_depth == 1 {
-                        key.pointer[1..].to_owned()
-                    } else {
-                        key.pointer.splitn(root_depth as usize + 1, '/').last().unwrap().to_owned()
-                    };
-                    match key.value_type {
-                        ValueType::Object(parsed, elements) => {
-                            if parsed {
-                                obj.insert(pointer.to_owned(), Value::Object(new_map(elements)));
-                            } else {
-                                obj.insert(pointer.to_owned(), Value::ObjectSerialized(value.unwrap()));
-                            }
-                        }
-                        ValueType::Array(len) => {
-                            if let Some(value) = value {
-                                obj.insert(pointer.to_owned(), Value::ArraySerialized(value));
-                            } else {
-                                obj.insert(pointer.to_owned(), Value::Array(Vec::with_capacity(len)));
-                            }
-                        }
-                        _ => {
-                            let value1 = value_to_json(value, &key.value_type);
-                            if !matches!(value1, Value::Null) {
-                                obj.insert(pointer.to_owned(), value1);
-                            }
-                        }
-                    }
-                }
-                Value::Array(array) => {
-                    match key.value_type {
-                        ValueType::Object(parsed, elements) => {
-                            if parsed {
-                                array.push(Value::Object(new_map(elements)));
-                            } else {
-                                array.push(Value::ObjectSerialized(value.unwrap()));
-                            }
-                        }
-                        ValueType::Array(len) => {
-                            if let Some(value) = value {
-                                array.push(Value::ArraySerialized(value));
-                            } else {
-                                array.push(Value::Array(Vec::with_capacity(len)));
-                            }
-                        }
-                        _ => {
-                            let value1 = value_to_json(value, &key.value_type);
-                            if !matches!(value1, Value::Null) {
-                                array.push(value1);
-                            }
-                        }
-                    }
-                }
-                _ => panic!("only Object is accepted for root node")
+    let mut root = if root_is_array { Node::Array(BTreeMap::new()) } else { Node::Object(Map::default()) };
+
+    for (segments, entry) in indexed {
+        let pointer = entry.key.pointer.as_str();
+        let mut parent = &mut root;
+        let last = segments.len() - 1;
+        for (i, segment) in segments[..last].iter().enumerate() {
+            let next_is_index = is_index(segments[i + 1]);
+            parent = child_node(parent, segment, pointer, || if next_is_index { Node::Array(BTreeMap::new()) } else { Node::Object(Map::default()) })?;
+        }
+
+        let node = match entry.key.value_type {
+            ValueType::Object(parsed, _) => match entry.value {
+                Some(value) if !parsed => Node::Leaf(Value::ObjectSerialized(value)),
+                _ => Node::Object(Map::default()),
+            },
+            ValueType::Array(_) => match entry.value {
+                Some(value) => Node::Leaf(Value::ArraySerialized(value)),
+                None => Node::Array(BTreeMap::new()),
+            },
+            value_type => Node::Leaf(scalar_to_json(entry.value, &value_type, options)),
+        };
+        expand_serialized(parent);
+        match parent {
+            Node::Object(obj) => {
+                obj.insert(segments[last].to_owned(), node);
             }
-        } else if key.depth > root_depth {
-            let split = key.pointer.split('/');
-            let key_pointer_iter = split.filter(|s| !s.is_empty());
-            let start: usize = root_depth as usize - 1;
-            let key_pointer_len = key_pointer_iter.clone().count();
-
-            let mut should_update_current_parent = true;
-            if key_pointer_len > 0 {
-                should_update_current_parent = if previous_parent_pointer.len() != key_pointer_len - 1 {
-                    true
-                } else {
-                    let mut matches = true;
-                    for (i, s) in key_pointer_iter.clone().enumerate() {
-                        if i < start {
-                            continue;
-                        }
-                        if i == key_pointer_len - 1 {
-                            break;
-                        }
-                        if !previous_parent_pointer[i].eq(s) {
-                            matches = false;
-                            break;
-                        }
-                    }
-                    !matches
-                };
+            Node::Array(array) => {
+                let index = usize::from_str(segments[last]).map_err(|_| format!("Entry {} is in an array but {} is not an index", pointer, segments[last]))?;
+                array.insert(index, node);
             }
+            Node::Leaf(_) => return Err(format!("Entry {} has a parent which is neither an object nor an array", pointer)),
+        }
+    }
+    Ok(to_value(root, &options.sparse_arrays))
+}
 
-            if should_update_current_parent {
-                previous_parent_pointer.clear();
-                if key_pointer_len > 0 {
-                    for (i, s) in key_pointer_iter.clone().enumerate() {
-                        if i < start {
-                            continue;
-                        }
-                        if i == key_pointer_len - 1 {
-                            break;
-                        }
-                        previous_parent_pointer.push(s.to_owned());
-                    }
-                }
-                let b = &previous_parent_pointer[0].as_bytes()[0];
-                if *b >= 0x30 && *b <= 0x39 {
-                    current_parent = &mut root_array;
-                } else {
-                    current_parent = &mut root;
-                }
-                for (i, s) in key_pointer_iter.clone().enumerate() {
-                    if i < start {
-                        continue;
-                    }
-                    if i == key_pointer_len - 1 {
-                        break;
-                    }
-                    match current_parent {
-                        Value::Object(ref mut obj) => {
-                            if obj.contains_key(s) {
-                                current_parent = obj.get_mut(s).unwrap();
-                            } else {
-                                obj.insert(s.to_owned(), Value::Object(new_map(10)));
-                                current_parent = obj.get_mut(s).unwrap();
-                            }
-                        }
-                        Value::Array(ref mut array) => {
-                            current_parent = array.get_mut(usize::from_str(s).unwrap())
-                                .unwrap();
-                            // .expect(format!("Expected to find parent at index for {}, current segment {}", key.pointer, s).as_str());
-                        }
-                        _ => panic!("only Object is accepted for root node")
-                    }
-                }
-            }
+fn child_node<'a, V>(parent: &'a mut Node<V>, segment: &str, pointer: &str, missing: impl FnOnce() -> Node<V>) -> Result<&'a mut Node<V>, String> {
+    expand_serialized(parent);
+    match parent {
+        Node::Object(obj) => Ok(obj.entry(segment.to_owned()).or_insert_with(missing)),
+        Node::Array(array) => {
+            let index = usize::from_str(segment).map_err(|_| format!("Entry {} is in an array but {} is not an index", pointer, segment))?;
+            Ok(array.entry(index).or_insert_with(missing))
+        }
+        Node::Leaf(_) => Err(format!("Entry {} has a parent which is neither an object nor an array", pointer)),
+    }
+}
 
-            let k = key_pointer_iter.last().unwrap();
-            match current_parent {
-                Value::Object(obj) => {
-                    match key.value_type {
-                        ValueType::Object(parsed, elements) => {
-                            if parsed {
-                                obj.insert(k.to_owned(), Value::Object(new_map(elements)));
-                            } else {
-                                obj.insert(k.to_owned(), Value::ObjectSerialized(value.unwrap()));
-                            }
-                        }
-                        ValueType::Array(len) => {
-                            if let Some(value) = value {
-                                obj.insert(k.to_owned(), Value::ArraySerialized(value));
-                            } else {
-                                obj.insert(k.to_owned(), Value::Array(Vec::with_capacity(len)));
-                            }
-                        }
-                        _ => {
-                            let value1 = value_to_json(value, &key.value_type);
-                            if !matches!(value1, Value::Null) {
-                                obj.insert(k.to_owned(), value1);
-                            }
-                        }
-                    }
-                }
-                Value::Array(array) => {
-                    match key.value_type {
-                        ValueType::Object(parsed, elements) => {
-                            if parsed {
-                                array.push(Value::Object(new_map(elements)));
-                            } else {
-                                array.push(Value::ObjectSerialized(value.unwrap()));
-                            }
-                        }
-                        ValueType::Array(len) => {
-                            if let Some(value) = value {
-                                array.push(Value::ArraySerialized(value));
-                            } else {
-                                array.push(Value::Array(Vec::with_capacity(len)));
-                            }
-                        }
-                        _ => {
-                            let value1 = value_to_json(value, &key.value_type);
-                            if !matches!(value1, Value::Null) {
-                                array.push(value1);
-                            }
-                        }
+fn to_value<V: Clone>(node: Node<V>, sparse_arrays: &SparseArrays<V>) -> Value<V> {
+    match node {
+        Node::Leaf(value) => value,
+        Node::Object(obj) => {
+            let mut map = new_map(obj.len());
+            for (key, child) in obj {
+                map.insert(key, to_value(child, sparse_arrays));
+            }
+            Value::Object(map)
+        }
+        Node::Array(array) => {
+            let mut values = Vec::with_capacity(array.len());
+            for (index, child) in array {
+                if let SparseArrays::Fill(filler) = sparse_arrays {
+                    while values.len() < index {
+                        values.push(filler.clone());
                     }
                 }
-                _ => panic!("only Object is accepted for root node")
+                values.push(to_value(child, sparse_arrays));
             }
+            Value::Array(values)
         }
     }
+}
 
-    if root_is_obj {
-        root
-    } else {
-        root_array
+// An array or an object kept serialized can still have children entries (e.g after change_depth), children win over raw data
+#[inline]
+fn expand_serialized<V>(node: &mut Node<V>) {
+    match node {
+        Node::Leaf(Value::ArraySerialized(_)) => *node = Node::Array(BTreeMap::new()),
+        Node::Leaf(Value::ObjectSerialized(_)) => *node = Node::Object(Map::default()),
+        _ => {}
     }
 }
 
+#[inline]
+fn is_index(segment: &str) -> bool {
+    !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit())
+}
+
 #[inline]
 fn new_map<V>(capacity: usize) -> Map<String, Value<V>> {
     #[cfg(feature = "indexmap")]{
@@ -280,15 +271,22 @@ fn new_map<V>(capacity: usize) -> Map<String, Value<V>> {
 }
 
 // Helper function to convert string values to JSON values based on ValueType
+// Numbers are kept as written with `raw_numbers`, except those JSON can't represent
+fn scalar_to_json<V: Debug + Clone + AsRef<str> + GetBytes>(value: Option<V>, value_type: &ValueType, options: &SerializeOptions<V>) -> Value<V> {
+    match value_type {
+        ValueType::Number if options.raw_numbers => value.filter(|n| json5_number_to_json(n.as_ref()).is_some()).map(Value::RawNumber).unwrap_or(Value::Null),
+        value_type => value_to_json(value, value_type),
+    }
+}
+
 fn value_to_json<V: Debug + Clone + AsRef<str> + GetBytes>(value: Option<V>, value_type: &ValueType) -> Value<V> {
     if let Some(value) = value {
         match value_type {
-            ValueType::Number => {
-                if let Ok(n) = value.as_ref().parse::<i64>() {
-                    Value::Integer(n)
-                } else {
-                    value.as_ref().parse::<f64>().map(Value::Number).unwrap_or(Value::Null)
-                }
+            // Json5 numbers are normalised first, NaN and Infinity are null like any number JSON can't represent
+            ValueType::Number => match json5_number_to_json(value.as_ref()) {
+                Some(number) => number.parse::<i64>().map(Value::Integer)
+                    .unwrap_or_else(|_| number.parse::<f64>().ok().filter(|n| n.is_finite()).map(Value::Number).unwrap_or(Value::Null)),
+                None => Value::Null,
             },
             ValueType::String => Value::String(value),
             ValueType::Bool => Value::Bool(value.as_ref() == "true" || value.as_ref() == "1"),
@@ -301,6 +299,7 @@ fn value_to_json<V: Debug + Clone + AsRef<str> + GetBytes>(value: Option<V>, val
 }
 
 impl<V: ToString + AsRef<str>> Value<V> {
+    /// Null members of objects are not written, they are kept in the `Value` tree
     pub fn to_json(&self) -> String {
         self._to_json(1)
     }
@@ -310,14 +309,13 @@ impl<V: ToString + AsRef<str>> Value<V> {
                 let members: Vec<String> = obj.iter()
                     .filter(|(_, v)| !matches!(v, Value::Null))
                     .map(|(k, v)| {
-                    format!("{:indent$}\"{}\": {}", "", k, v._to_json(depth + 1), indent = depth * 2)
+                    format!("{:indent$}\"{}\": {}", "", json5_string_to_json(k), v._to_json(depth + 1), indent = depth * 2)
                 }).collect();
                 format!("{{\n{}\n{:indent$}}}", members.join(",\n"), "", indent = (depth - 1) * 2)
             }
             Value::Array(arr) => {
                 let mut contains_nested_array = false;
                 let elements: Vec<String> = arr.iter()
-                    .filter(|v| !matches!(v, Value::Null))
                     .map(|v| {
                     if matches!(v, Value::Array(_)) || matches!(v, Value::Object(_)) {
                         contains_nested_array = true;
@@ -332,9 +330,11 @@ impl<V: ToString + AsRef<str>> Value<V> {
                     format!("[{}]", elements.join(", "))
                 }
             }
+            Value::Number(num) if !num.is_finite() => "null".to_string(),
             Value::Number(num) => num.to_string(),
             Value::Integer(num) => num.to_string(),
-            Value::String(s) => format!("\"{}\"", s.as_ref().replace('\"', "\\\"")),
+            Value::RawNumber(num) => json5_number_to_json(num.as_ref()).map_or_else(|| "null".to_string(), |n| n.into_owned()),
+            Value::String(s) => format!("\"{}\"", json5_string_to_json(s.as_ref())),
             Value::Bool(b) => b.to_string(),
             Value::Null => "null".to_string(),
             Value::ArraySerialized(value) => value.to_string(),
@@ -342,13 +342,293 @@ impl<V: ToString + AsRef<str>> Value<V> {
             _ => panic!("todo")
         }
     }
+
+    /// Same as `to_json` without any whitespace
+    pub fn to_json_compact(&self) -> String {
+        self._to_json_compact(false)
+    }
+    // JSONL rows keep their null members, an edited row is written with all its data
+    fn _to_json_compact(&self, keep_null_members: bool) -> String {
+        match self {
+            Value::Object(obj) => {
+                let members: Vec<String> = obj.iter()
+                    .filter(|(_, v)| keep_null_members || !matches!(v, Value::Null))
+                    .map(|(k, v)| format!("\"{}\":{}", json5_string_to_json(k), v._to_json_compact(keep_null_members)))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            }
+            Value::Array(arr) => {
+                let elements: Vec<String> = arr.iter()
+                    .map(|v| v._to_json_compact(keep_null_members))
+                    .collect();
+                format!("[{}]", elements.join(","))
+            }
+            _ => self._to_json(1)
+        }
+    }
+}
+
+/// Serialize the value at `pointer` ("" for the whole document), entries under it are cloned and re-rooted, `result` is left untouched.
+pub fn serialize_subtree<V: Debug + Clone + AsRef<str> + GetBytes>(result: &ParseResult<V>, pointer: &str) -> Result<Value<V>, String> {
+    serialize_subtree_with_options(result, pointer, &SerializeOptions::default())
+}
+
+/// `root_depth` and `root_is_array` of `options` are not used, the subtree is always re-rooted at `pointer`.
+pub fn serialize_subtree_with_options<V: Debug + Clone + AsRef<str> + GetBytes>(result: &ParseResult<V>, pointer: &str, options: &SerializeOptions<V>) -> Result<Value<V>, String> {
+    let options = SerializeOptions { root_depth: 1, root_is_array: false, ..options.clone() };
+    if pointer.is_empty() {
+        let mut data = result.json.clone();
+        return _serialize_to_json(&mut data, &options);
+    }
+    let prefix = concat_string!(pointer, "/");
+    let entry = result.json.iter().find(|e| e.pointer.pointer.eq(pointer));
+    let mut data: Vec<FlatJsonValue<V>> = Vec::with_capacity(16);
+    if let Some(entry) = entry {
+        match entry.pointer.value_type {
+            ValueType::Array(len) => data.push(FlatJsonValue { pointer: PointerKey::from_pointer(String::new(), ValueType::Array(len), 1, 0), value: None }),
+            ValueType::Object(..) => {}
+            value_type => return Ok(scalar_to_json(entry.value.clone(), &value_type, &options)),
+        }
+    }
+    for e in result.json.iter().filter(|e| e.pointer.pointer.starts_with(prefix.as_str())) {
+        let child_pointer = e.pointer.pointer[pointer.len()..].to_string();
+        let depth = child_pointer.matches('/').count() as u8;
+        data.push(FlatJsonValue { pointer: PointerKey::from_pointer(child_pointer, e.pointer.value_type, depth, data.len()), value: e.value.clone() });
+    }
+    match entry {
+        None if data.is_empty() => Err(format!("No entry found at {}", pointer)),
+        // Not parsed further, raw data is the only source
+        Some(entry) if data.iter().all(|e| e.pointer.pointer.is_empty()) && entry.value.is_some() => {
+            let raw = entry.value.clone().unwrap();
+            Ok(if matches!(entry.pointer.value_type, ValueType::Array(_)) { Value::ArraySerialized(raw) } else { Value::ObjectSerialized(raw) })
+        }
+        _ => _serialize_to_json(&mut data, &options),
+    }
+}
+
+/// Write `result` as JSONL, one compact JSON document per root array element.
+/// Rows which have not been modified since parsing are written from the raw data kept with `keep_object_raw_data`,
+/// other rows are serialized again from their flat entries.
+pub fn write_jsonl<V: Debug + Clone + AsRef<str> + GetBytes + ToString>(result: &ParseResult<V>, writer: impl Write) -> io::Result<()> {
+    write_jsonl_with_options(result, &SerializeOptions::default(), writer)
+}
+
+/// Same as `write_jsonl`, rows serialized again use `options`, e.g `raw_numbers` to write their numbers as parsed.
+/// `root_depth` and `root_is_array` of `options` are not used.
+pub fn write_jsonl_with_options<V: Debug + Clone + AsRef<str> + GetBytes + ToString>(result: &ParseResult<V>, options: &SerializeOptions<V>, mut writer: impl Write) -> io::Result<()> {
+    let options = SerializeOptions { root_depth: 1, root_is_array: false, ..options.clone() };
+    let mut rows: BTreeMap<usize, Vec<&FlatJsonValue<V>>> = BTreeMap::new();
+    for entry in result.json.iter() {
+        let Some(row_index) = entry.pointer.pointer.split('/').nth(1).and_then(|s| usize::from_str(s).ok()) else {
+            continue;
+        };
+        rows.entry(row_index).or_default().push(entry);
+    }
+
+    for (row_index, entries) in rows {
+        let row_pointer = format!("/{}", row_index);
+        let row = entries.iter().find(|e| e.pointer.pointer.eq(&row_pointer));
+        match row {
+            Some(row) if !matches!(row.pointer.value_type, ValueType::Object(..) | ValueType::Array(_)) => {
+                let value = scalar_to_json(row.value.clone(), &row.pointer.value_type, &options);
+                writer.write_all(value.to_json_compact().as_bytes())?;
+            }
+            // Row has not been expanded, its raw data is the only source
+            Some(row) if row.value.is_some() && entries.len() == 1 => {
+                writer.write_all(row.value.as_ref().unwrap().get_bytes())?;
+            }
+            Some(row) if row.value.is_some() && row_unchanged(row_index, row.value.as_ref().unwrap().as_ref(), &entries, result) => {
+                writer.write_all(row.value.as_ref().unwrap().get_bytes())?;
+            }
+            _ => {
+                let value = serialize_row(&row_pointer, row, &entries, &options).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                writer.write_all(value._to_json_compact(true).as_bytes())?;
+            }
+        }
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Write `source` with the values of `result` which differ from it spliced in place, everything else (whitespace, key order...) is copied as is.
+/// `result` must come from parsing `source`: entries are located with their span. Only values can be edited, added or removed entries are not written.
+pub fn write_with_edits<V: Debug + Clone + AsRef<str> + GetBytes>(source: &[u8], result: &ParseResult<V>, mut writer: impl Write) -> io::Result<()> {
+    let mut edits: Vec<(&Range<usize>, Cow<[u8]>)> = Vec::new();
+    for entry in result.json.iter() {
+        let value = entry.value.as_ref().map(|v| v.as_ref());
+        let text: Cow<[u8]> = match (&entry.pointer.value_type, value) {
+            (ValueType::String, Some(value)) => Cow::Owned([b"\"", json5_string_to_json(value).as_bytes(), b"\""].concat()),
+            (ValueType::Number | ValueType::Bool, Some(value)) | (ValueType::Object(false, _) | ValueType::Array(_), Some(value)) => Cow::Borrowed(value.as_bytes()),
+            (ValueType::Null, _) => Cow::Borrowed(b"null"),
+            // Parsed objects and arrays are edited through their children
+            _ => continue,
+        };
+        let Some(span) = entry.pointer.span.as_ref() else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Entry {} has no span in source, only values of parsed entries can be edited", entry.pointer.pointer)));
+        };
+        if span.end > source.len() || span.start > span.end {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Span of entry {} is outside of source", entry.pointer.pointer)));
+        }
+        let written = &source[span.clone()];
+        // Strings are compared without their quotes, so an unchanged Json5 single-quoted string is left as written
+        let unchanged = match (&entry.pointer.value_type, value) {
+            (ValueType::String, Some(value)) => written.len() >= 2 && matches!(written[0], b'"' | b'\'') && written[1..written.len() - 1] == *value.as_bytes(),
+            _ => *written == *text,
+        };
+        if !unchanged {
+            edits.push((span, text));
+        }
+    }
+    edits.sort_by_key(|(span, _)| span.start);
+
+    let mut copied = 0;
+    for (span, text) in edits {
+        if span.start < copied {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Edit at {}..{} overlaps a previous edit", span.start, span.end)));
+        }
+        writer.write_all(&source[copied..span.start])?;
+        writer.write_all(&text)?;
+        copied = span.end;
+    }
+    writer.write_all(&source[copied..])?;
+    writer.flush()
+}
+
+/// Write `result` as indented Jsonc, with the comments captured when parsing Jsonc or Json5 input written back around their entry:
+/// leading comments on the lines before it, trailing comments at the end of its line (after the opening bracket for parsed objects and arrays).
+/// Entries must be in document order, as returned by the parser. Unlike `Value::to_json`, null members of objects are kept.
+/// Json5 strings and numbers are written as JSON, like `Value::to_json` does. This is the only writer keeping comments, `Value` has no place for them.
+pub fn write_jsonc<V: Debug + Clone + AsRef<str> + GetBytes>(result: &ParseResult<V>, mut writer: impl Write) -> io::Result<()> {
+    // Containers being written: pointer, is array, has children
+    let mut open: Vec<(&str, bool, bool)> = Vec::with_capacity(16);
+    // Comments to write at the end of the current line
+    let mut trailing: &[String] = &[];
+    let entries = &result.json;
+    if !entries.first().is_some_and(|e| e.pointer.pointer.is_empty()) {
+        writer.write_all(b"{")?;
+        open.push(("", false, false));
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let pointer = entry.pointer.pointer.as_str();
+        let comments = entry.pointer.comments.as_deref();
+        let leading = comments.map_or(&[][..], |c| &c.leading);
+        if let Some(parent_end) = pointer.rfind('/') {
+            while open.last().is_some_and(|(parent, _, _)| *parent != &pointer[..parent_end]) {
+                close_container(&mut open, &mut trailing, &mut writer)?;
+            }
+            let Some((_, is_array, has_children)) = open.last_mut() else {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Entry {} is not after its parent", pointer)));
+            };
+            let is_array = *is_array;
+            if *has_children {
+                writer.write_all(b",")?;
+            }
+            *has_children = true;
+            write_trailing_comments(mem::take(&mut trailing), &mut writer)?;
+            let indent = open.len() * 2;
+            for comment in leading {
+                write!(writer, "\n{:indent$}{}", "", comment, indent = indent)?;
+            }
+            write!(writer, "\n{:indent$}", "", indent = indent)?;
+            if !is_array {
+                write!(writer, "\"{}\": ", json5_string_to_json(&pointer[parent_end + 1..]))?;
+            }
+        } else {
+            // root array
+            for comment in leading {
+                writeln!(writer, "{}", comment)?;
+            }
+        }
+
+        let has_children = entries.get(i + 1).is_some_and(|next| next.pointer.pointer.len() > pointer.len()
+            && next.pointer.pointer.starts_with(pointer) && next.pointer.pointer.as_bytes()[pointer.len()] == b'/');
+        match (&entry.pointer.value_type, entry.value.as_ref()) {
+            (ValueType::Object(..), _) if has_children => {
+                writer.write_all(b"{")?;
+                open.push((pointer, false, false));
+            }
+            (ValueType::Array(_), _) if has_children => {
+                writer.write_all(b"[")?;
+                open.push((pointer, true, false));
+            }
+            (ValueType::String, Some(value)) => write!(writer, "\"{}\"", json5_string_to_json(value.as_ref()))?,
+            (ValueType::Number, Some(value)) => writer.write_all(json5_number_to_json(value.as_ref()).as_deref().unwrap_or("null").as_bytes())?,
+            (ValueType::Object(..) | ValueType::Array(_) | ValueType::Bool, Some(value)) => writer.write_all(value.as_ref().as_bytes())?,
+            (ValueType::Object(..), None) => writer.write_all(b"{}")?,
+            (ValueType::Array(_), None) => writer.write_all(b"[]")?,
+            _ => writer.write_all(b"null")?,
+        }
+        trailing = comments.map_or(&[][..], |c| &c.trailing);
+    }
+    while !open.is_empty() {
+        close_container(&mut open, &mut trailing, &mut writer)?;
+    }
+    write_trailing_comments(trailing, &mut writer)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+fn close_container(open: &mut Vec<(&str, bool, bool)>, trailing: &mut &[String], mut writer: impl Write) -> io::Result<()> {
+    let Some((_, is_array, has_children)) = open.pop() else {
+        return Ok(());
+    };
+    write_trailing_comments(mem::take(trailing), &mut writer)?;
+    if has_children {
+        write!(writer, "\n{:indent$}", "", indent = open.len() * 2)?;
+    }
+    writer.write_all(if is_array { b"]" } else { b"}" })
+}
+
+fn write_trailing_comments(comments: &[String], mut writer: impl Write) -> io::Result<()> {
+    for comment in comments {
+        write!(writer, " {}", comment)?;
+    }
+    Ok(())
+}
+
+/// Whether `entries` are what parsing `raw` again with the options `result` was parsed with gives
+fn row_unchanged<V: Debug + Clone + AsRef<str> + GetBytes>(row_index: usize, raw: &str, entries: &[&FlatJsonValue<V>], result: &ParseResult<V>) -> bool {
+    let options = ParseOptions::default().max_depth(result.parsing_max_depth).parse_array(result.parse_array).keep_object_raw_data(result.keep_object_raw_data);
+
+    let mut parsed: Vec<FlatJsonValue<&str>> = Vec::with_capacity(entries.len());
+    if split_row(raw.as_bytes()).and_then(|element| parse_row(row_index, element, &options, &mut parsed)).is_err() {
+        return false;
+    }
+    if parsed.len() != entries.len() {
+        return false;
+    }
+    let mut entries = entries.to_vec();
+    entries.sort_unstable_by(|a, b| a.pointer.pointer.cmp(&b.pointer.pointer));
+    parsed.sort_unstable_by(|a, b| a.pointer.pointer.cmp(&b.pointer.pointer));
+    entries.iter().zip(parsed.iter()).all(|(entry, parsed)| {
+        entry.pointer.pointer.eq(&parsed.pointer.pointer)
+            && entry.pointer.value_type.eq(&parsed.pointer.value_type)
+            && entry.value.as_ref().map(|v| v.as_ref()).eq(&parsed.value)
+    })
+}
+
+/// Serialize a row from its flat entries, re-rooting them at the row pointer
+fn serialize_row<V: Debug + Clone + AsRef<str> + GetBytes>(row_pointer: &str, row: Option<&&FlatJsonValue<V>>, entries: &[&FlatJsonValue<V>], options: &SerializeOptions<V>) -> Result<Value<V>, String> {
+    let mut data: Vec<FlatJsonValue<V>> = Vec::with_capacity(entries.len());
+    if let Some(row) = row {
+        if let ValueType::Array(len) = row.pointer.value_type {
+            data.push(FlatJsonValue { pointer: PointerKey::from_pointer(String::new(), ValueType::Array(len), 0, 0), value: None });
+        }
+    }
+    for entry in entries.iter().filter(|e| e.pointer.pointer.len() > row_pointer.len()) {
+        let mut entry = (*entry).clone();
+        entry.pointer.pointer = entry.pointer.pointer[row_pointer.len()..].to_string();
+        entry.pointer.depth -= 1;
+        data.push(entry);
+    }
+    _serialize_to_json(&mut data, options)
 }
 
 #[cfg(test)]
 #[cfg(feature = "indexmap")] // to ease testing we use indexmap to have deterministic output
 mod tests {
-    use crate::{FlatJsonValue, JSONParser, ParseOptions, PointerKey, ValueType};
-    use crate::serializer::{serialize_to_json, serialize_to_json_with_option};
+    use crate::{Dialect, FlatJsonValue, JSONParser, ParseOptions, ParseResult, PointerKey, ValueType};
+    use crate::serializer::{serialize_slice, serialize_slice_with_options, serialize_subtree, serialize_subtree_with_options, serialize_to_json, serialize_to_json_with_option, serialize_to_json_with_options, write_jsonc, write_jsonl, write_jsonl_with_options, write_with_edits, SerializeOptions, SparseArrays, Value};
 
 
     #[test]
@@ -384,7 +664,7 @@ let expected =
 }"#;
 
         let mut vec = JSONParser::parse(json, ParseOptions::default()).unwrap().json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), expected);
     }
     #[test]
@@ -416,6 +696,9 @@ let expected =
                 depth: 2,
                 position: 0,
                 column_id: 0,
+                span: None,
+                key_span: None,
+                comments: None,
             },
             value: Some("true"),
         });
@@ -429,7 +712,7 @@ let expected =
         //     },
         //     value: Some("11"),
         // });
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), expectation);
     }
 
@@ -456,7 +739,7 @@ let expected =
         };
         let mut vec = result.json;
         vec[0].value = Some("12");
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         println!("{:?}", value.to_json());
     }
 
@@ -467,7 +750,7 @@ let expected =
 
         let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), json);
     }
 
@@ -504,7 +787,7 @@ let expected =
 
         let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), json);
     }
 
@@ -518,7 +801,7 @@ let expected =
 
         let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), json);
     }
 
@@ -682,7 +965,7 @@ let expected =
 }"#;
         let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), json);
 
         let res = JSONParser::parse(json, ParseOptions::default().max_depth(2)).unwrap();
@@ -692,14 +975,14 @@ let expected =
         let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
         let vec = res.json;
         let mut vec: Vec<FlatJsonValue<&str>> = vec.iter().filter(|entry| entry.pointer.depth >= 3 && entry.pointer.pointer.starts_with("/skills/0")).cloned().collect::<Vec<FlatJsonValue<&str>>>();
-        let value = serialize_to_json_with_option(&mut vec, 3);
+        let value = serialize_to_json_with_option(&mut vec, 3).unwrap();
         assert_eq!(value.to_json().replace(' ', ""), json_depth_2[1].value.unwrap().replace(' ', ""));
 
         // Test partial serialization of nested object
         let res = JSONParser::parse(json, ParseOptions::default()).unwrap();
         let vec = res.json;
         let mut vec: Vec<FlatJsonValue<&str>> = vec.iter().filter(|entry| entry.pointer.depth >= 3 && entry.pointer.pointer.starts_with("/skills/1")).cloned().collect::<Vec<FlatJsonValue<&str>>>();
-        let value = serialize_to_json_with_option(&mut vec, 3);
+        let value = serialize_to_json_with_option(&mut vec, 3).unwrap();
         assert_eq!(value.to_json().replace(' ', ""), json_depth_2[2].value.unwrap().replace(' ', ""));
 
 
@@ -717,10 +1000,13 @@ let expected =
                 depth: 0,
                 position: 0,
                 column_id: 0,
+                span: None,
+                key_span: None,
+                comments: None,
             },
             value: None,
         });
-        let value = serialize_to_json_with_option(&mut vec, 4);
+        let value = serialize_to_json_with_option(&mut vec, 4).unwrap();
         assert_eq!(value.to_json().replace(' ', ""), json_depth_2[16].value.unwrap().replace(' ', ""));
 
         // Test partial serialization of nested parsed array under object
@@ -734,10 +1020,13 @@ let expected =
                 depth: 0,
                 position: 0,
                 column_id: 0,
+                span: None,
+                key_span: None,
+                comments: None,
             },
             value: None,
         });
-        let value = serialize_to_json_with_option(&mut vec, 5);
+        let value = serialize_to_json_with_option(&mut vec, 5).unwrap();
         assert_eq!(value.to_json().replace(' ', ""), json_depth_2[15].value.unwrap().replace(' ', ""));
     }
 
@@ -883,7 +1172,7 @@ let expected =
 }"#;
         let res = JSONParser::parse(json, ParseOptions::default().start_parse_at("/skills".to_string()).parse_array(false)).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), json);
     }
 
@@ -1029,7 +1318,7 @@ let expected =
 
         let res = JSONParser::parse(json, ParseOptions::default().max_depth(1).parse_array(true)).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), json);
     }
 
@@ -1176,7 +1465,7 @@ let expected =
 
         let res = JSONParser::parse(json, ParseOptions::default().max_depth(1).parse_array(false)).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), json);
     }
 
@@ -1326,11 +1615,46 @@ let expected =
 
         let res = JSONParser::parse(json, ParseOptions::default().max_depth(2).start_parse_at("/skills".to_string()).parse_array(false)).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         assert_eq!(value.to_json(), json);
     }
 
 
+    #[test]
+    fn write_jsonl_round_trip() {
+        let content = "{\"id\": 1,   \"user\": {\"name\": \"a\"}}\n[1,  2]\n\"str\"\n42\n{\"id\": 2, \"tags\": [\"x\"]}\n";
+        let result = JSONParser::parse_jsonl(content.as_bytes(), ParseOptions::default()).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonl(&result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), content);
+
+        let result = JSONParser::parse_jsonl(content.as_bytes(), ParseOptions::default().max_depth(2).parse_array(false)).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonl(&result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), content);
+    }
+
+    #[test]
+    fn write_jsonl_modified_rows() {
+        let content = "{\"id\": 1,   \"user\": {\"name\": \"a\"}}\n[1,  2]\n{\"id\": 2, \"tags\": [\"x\"]}\n";
+        let mut result = JSONParser::parse_jsonl(content.as_bytes(), ParseOptions::default()).unwrap();
+        result.json.iter_mut().find(|e| e.pointer.pointer.eq("/0/user/name")).unwrap().value = Some("b".to_string());
+        result.json.iter_mut().find(|e| e.pointer.pointer.eq("/1/1")).unwrap().value = Some("3".to_string());
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonl(&result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"id\":1,\"user\":{\"name\":\"b\"}}\n[1,3]\n{\"id\": 2, \"tags\": [\"x\"]}\n");
+    }
+
+    #[test]
+    fn write_jsonl_modified_rows_keep_nulls() {
+        let content = "{\"a\": null, \"b\": 1, \"c\": {\"d\": null}}\n";
+        let mut result = JSONParser::parse_jsonl(content.as_bytes(), ParseOptions::default()).unwrap();
+        result.json.iter_mut().find(|e| e.pointer.pointer.eq("/0/b")).unwrap().value = Some("2".to_string());
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonl(&result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"a\":null,\"b\":2,\"c\":{\"d\":null}}\n");
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn serde_serialization() {
@@ -1478,10 +1802,252 @@ let expected =
 
         let res = JSONParser::parse(json, ParseOptions::default().max_depth(2).start_parse_at("/skills".to_string()).parse_array(false)).unwrap();
         let mut vec = res.json;
-        let value = serialize_to_json(&mut vec);
+        let value = serialize_to_json(&mut vec).unwrap();
         let res = serde_json::to_string_pretty(&value);
         assert_eq!(res.unwrap(), json);
     }
+
+    #[test]
+    fn missing_parents_and_sparse_arrays() {
+        let json = r#"{"a": {"list": [{"id": 1}, {"id": 2}, {"id": 3, "tags": ["x", null, "z"]}]}, "b": 1}"#;
+        let result = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let filtered = |keep: &dyn Fn(&str) -> bool| result.json.iter().filter(|e| keep(e.pointer.pointer.as_str())).cloned().collect::<Vec<FlatJsonValue<&str>>>();
+
+        let mut vec = filtered(&|p| p.starts_with("/a/list/2"));
+        assert_eq!(serialize_to_json(&mut vec).unwrap().to_json_compact(), r#"{"a":{"list":[null,null,{"id":3,"tags":["x",null,"z"]}]}}"#);
+        let mut vec = filtered(&|p| p.starts_with("/a/list/") && !p.starts_with("/a/list/1"));
+        let options = SerializeOptions::default().sparse_arrays(SparseArrays::Compact);
+        assert_eq!(serialize_to_json_with_options(&mut vec, &options).unwrap().to_json_compact(), r#"{"a":{"list":[{"id":1},{"id":3,"tags":["x",null,"z"]}]}}"#);
+        let mut vec = filtered(&|p| p.ends_with("/id"));
+        let options = SerializeOptions::default().sparse_arrays(SparseArrays::Fill(Value::Object(Default::default())));
+        assert_eq!(serialize_to_json_with_options(&mut vec, &options).unwrap().to_json_compact(), r#"{"a":{"list":[{"id":1},{"id":2},{"id":3}]}}"#);
+
+        let mut vec = filtered(&|p| p.starts_with("/a/list/"));
+        let options = SerializeOptions::default().root_depth(3).sparse_arrays(SparseArrays::Compact).root_is_array(true);
+        assert_eq!(serialize_to_json_with_options(&mut vec, &options).unwrap().to_json_compact(), r#"[{"id":1},{"id":2},{"id":3,"tags":["x",null,"z"]}]"#);
+
+        // Root object with index-like keys stays an object
+        let result = JSONParser::parse(r#"{"0": "a", "1": {"0": "b"}}"#, ParseOptions::default()).unwrap();
+        assert_eq!(serialize_slice(&result.json).unwrap().to_json_compact(), r#"{"0":"a","1":{"0":"b"}}"#);
+        assert_eq!(serialize_subtree(&result, "/1").unwrap().to_json_compact(), r#"{"0":"b"}"#);
+
+        let mut vec = vec![FlatJsonValue { pointer: PointerKey::from_pointer("/b".to_string(), ValueType::Number, 1, 0), value: Some("1") },
+                           FlatJsonValue { pointer: PointerKey::from_pointer("/b/c".to_string(), ValueType::Number, 2, 1), value: Some("2") }];
+        assert_eq!(serialize_to_json(&mut vec).err().unwrap(), "Entry /b/c has a parent which is neither an object nor an array");
+        let mut vec = vec![FlatJsonValue { pointer: PointerKey::from_pointer("".to_string(), ValueType::Array(1), 1, 0), value: None },
+                           FlatJsonValue { pointer: PointerKey::from_pointer("/b".to_string(), ValueType::Number, 1, 1), value: Some("1") }];
+        assert_eq!(serialize_to_json(&mut vec).err().unwrap(), "Entry /b is in an array but b is not an index");
+    }
+
+    #[test]
+    fn subtree() {
+        let json = r#"{"components": {"schemas": {"Pet": {"type": "object", "required": ["id", "name"], "properties": {"id": {"type": "integer"}}}}}, "paths": {}}"#;
+        let result = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let before: Vec<String> = result.json.iter().map(|e| e.pointer.pointer.clone()).collect();
+
+        assert_eq!(serialize_subtree(&result, "/components/schemas/Pet").unwrap().to_json_compact(),
+                   r#"{"type":"object","required":["id","name"],"properties":{"id":{"type":"integer"}}}"#);
+        assert_eq!(serialize_subtree(&result, "/components/schemas/Pet/required").unwrap().to_json_compact(), r#"["id","name"]"#);
+        assert_eq!(serialize_subtree(&result, "/components/schemas/Pet/type").unwrap().to_json_compact(), r#""object""#);
+        assert_eq!(serialize_subtree(&result, "/paths").unwrap().to_json_compact(), "{}");
+        assert_eq!(serialize_subtree(&result, "").unwrap().to_json_compact(), serialize_to_json(&mut result.json.clone()).unwrap().to_json_compact());
+        assert_eq!(serialize_subtree(&result, "/components/schemas/Dog").err().unwrap(), "No entry found at /components/schemas/Dog");
+        assert_eq!(result.json.iter().map(|e| e.pointer.pointer.clone()).collect::<Vec<String>>(), before);
+
+        // Raw data when subtree has not been parsed
+        let result = JSONParser::parse(json, ParseOptions::default().max_depth(3)).unwrap();
+        assert_eq!(serialize_subtree(&result, "/components/schemas/Pet").unwrap().to_json_compact().replace(' ', ""),
+                   r#"{"type":"object","required":["id","name"],"properties":{"id":{"type":"integer"}}}"#);
+    }
+
+    #[test]
+    fn slice_is_left_untouched() {
+        let json = r#"{"a": {"list": [{"id": 1}, {"id": 2, "tags": ["x", null]}]}, "b": "text", "c": 1.5}"#;
+        let result = JSONParser::parse(json, ParseOptions::default()).unwrap().to_owned();
+        let before: Vec<(String, usize)> = result.json.iter().map(|e| (e.pointer.pointer.clone(), e.pointer.position)).collect();
+
+        let value = serialize_slice(&result.json).unwrap();
+        assert_eq!(value.to_json_compact(), r#"{"a":{"list":[{"id":1},{"id":2,"tags":["x",null]}]},"b":"text","c":1.5}"#);
+        assert_eq!(result.json.iter().map(|e| (e.pointer.pointer.clone(), e.pointer.position)).collect::<Vec<(String, usize)>>(), before);
+        assert_eq!(value.to_json(), serialize_to_json(&mut result.json.clone()).unwrap().to_json());
+
+        let options = SerializeOptions::default().root_depth(3).root_is_array(true);
+        assert_eq!(serialize_slice_with_options(&result.json[2..], &options).unwrap().to_json_compact(), r#"[{"id":1},{"id":2,"tags":["x",null]}]"#);
+    }
+
+    #[test]
+    fn raw_numbers() {
+        let json = r#"{"id": 12345678901234567890, "amount": 1.10, "huge": 1e400, "small": -0.0, "list": [1.50, 2]}"#;
+        let result = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let options = SerializeOptions::default().raw_numbers(true);
+        let value = serialize_slice_with_options(&result.json, &options).unwrap();
+        assert_eq!(value.to_json_compact(), json.replace(' ', ""));
+        assert!(matches!(value, Value::Object(ref obj) if matches!(obj.get("amount"), Some(Value::RawNumber("1.10")))));
+
+        // Parsed by default
+        assert!(serialize_slice(&result.json).unwrap().to_json_compact().contains(r#""amount":1.1,"#));
+
+        let mut rows = JSONParser::parse_jsonl(b"{\"a\": 1.10, \"b\": 1}\n1.0\n", ParseOptions::default().keep_object_raw_data(false)).unwrap();
+        rows.json.retain(|e| e.pointer.pointer != "/0/b");
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonl_with_options(&rows, &SerializeOptions::default().raw_numbers(true), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"a\":1.10}\n1.0\n");
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonl(&rows, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"a\":1.1}\n1\n");
+
+        assert_eq!(serialize_subtree_with_options(&result, "/amount", &SerializeOptions::default().raw_numbers(true)).unwrap().to_json(), "1.10");
+        assert_eq!(serialize_subtree_with_options(&result, "/list", &SerializeOptions::default().raw_numbers(true)).unwrap().to_json_compact(), "[1.50,2]");
+        assert_eq!(serialize_subtree(&result, "/amount").unwrap().to_json(), "1.1");
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn raw_numbers_arbitrary_precision() {
+        let json = r#"{"id": 12345678901234567890123, "amount": 1.10}"#;
+        let result = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let value = serialize_slice_with_options(&result.json, &SerializeOptions::default().raw_numbers(true)).unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), r#"{"id":12345678901234567890123,"amount":1.10}"#);
+        assert_eq!(serde_json::Value::try_from(value).unwrap().to_string(), r#"{"amount":1.10,"id":12345678901234567890123}"#);
+    }
+
+    #[test]
+    fn edits_keep_formatting() {
+        let json = "{\n  \"name\":   \"server\",\n  \"port\": 8080,\n  \"tls\": {\"enabled\": false, \"cert\": null},\n  \"tags\": [\"a\",\n    \"b\"]\n}\n";
+        let mut result = JSONParser::parse(json, ParseOptions::default().max_depth(2).parse_array(false)).unwrap().to_owned();
+        let mut out: Vec<u8> = Vec::new();
+        write_with_edits(json.as_bytes(), &result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), json);
+
+        let entry = |result: &ParseResult<String>, pointer: &str| result.json.iter().position(|e| e.pointer.pointer == pointer).unwrap();
+        let port = entry(&result, "/port");
+        result.json[port].value = Some("9090".to_string());
+        let enabled = entry(&result, "/tls/enabled");
+        result.json[enabled].value = Some("true".to_string());
+        let cert = entry(&result, "/tls/cert");
+        result.json[cert].pointer.value_type = ValueType::String;
+        result.json[cert].value = Some("/etc/\\\"cert\\\".pem".to_string());
+        let tags = entry(&result, "/tags");
+        result.json[tags].value = Some("[\"c\"]".to_string());
+        let mut out: Vec<u8> = Vec::new();
+        write_with_edits(json.as_bytes(), &result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "{\n  \"name\":   \"server\",\n  \"port\": 9090,\n  \"tls\": {\"enabled\": true, \"cert\": \"/etc/\\\"cert\\\".pem\"},\n  \"tags\": [\"c\"]\n}\n");
+
+        result.json.push(FlatJsonValue { pointer: PointerKey::from_pointer("/added".to_string(), ValueType::Number, 1, 10), value: Some("1".to_string()) });
+        let error = write_with_edits(json.as_bytes(), &result, &mut Vec::new()).err().unwrap();
+        assert_eq!(error.to_string(), "Entry /added has no span in source, only values of parsed entries can be edited");
+    }
+
+    #[test]
+    fn edits_keep_jsonc_and_json5_source() {
+        let jsonc = "// config\n{\n  \"a\": [1, 2,], /* two */\n  \"b\": \"say \\\"hi\\\"\",\n}\n";
+        let json5 = "{s: 'say \"hi\"', t: 'it\\'s', n: 0x1F, m: +.5, i: -Infinity, // end\n}";
+        for (json, dialect) in [(jsonc, crate::Dialect::Jsonc), (json5, crate::Dialect::Json5)] {
+            let result = JSONParser::parse(json, ParseOptions::default().dialect(dialect)).unwrap();
+            let mut out: Vec<u8> = Vec::new();
+            write_with_edits(json.as_bytes(), &result, &mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), json);
+        }
+
+        let mut result = JSONParser::parse(json5, ParseOptions::default().dialect(crate::Dialect::Json5)).unwrap();
+        result.json[1].value = Some("it's \"new\"");
+        let mut out: Vec<u8> = Vec::new();
+        write_with_edits(json5.as_bytes(), &result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{s: 'say \"hi\"', t: \"it's \\\"new\\\"\", n: 0x1F, m: +.5, i: -Infinity, // end\n}");
+    }
+
+    #[test]
+    fn jsonc_comments_are_written_back() {
+        let json = r#"{
+  // leading of a
+  "a": 1, // trailing of a
+  "b": { /* trailing of b */
+    "c": [
+      true, /* trailing of 0 */
+      // leading of 1
+      false // end of c
+    ],
+    "n": null
+  },
+  "d": [] // trailing of d
+}
+"#;
+        let result = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Jsonc)).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonc(&result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), json);
+
+        // Comments keep their entry when they move, e.g before root value
+        let json = "// note\n[1, {\"a\": 2} /* about a */]";
+        let result = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Jsonc).max_depth(1)).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonc(&result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "// note\n[\n  1,\n  {\"a\": 2} /* about a */\n]\n");
+
+        // Json5 values are written as JSON
+        let json = "{'a\"b': 'say \"hi\"', t: \"it's\", u: 'it\\'s', n: 0x1F, m: +.5, nan: NaN // not a number\n}";
+        let result = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Json5)).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_jsonc(&result, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "{\n  \"a\\\"b\": \"say \\\"hi\\\"\",\n  \"t\": \"it's\",\n  \"u\": \"it's\",\n  \"n\": 31,\n  \"m\": 0.5,\n  \"nan\": null // not a number\n}\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json5_is_written_as_json() {
+        let json = r#"{s: 'say "hi"', t: 'it\'s', d: "a \"b\"", hex: 0x1F, plus: +1, dot: .5, nan: NaN, inf: -Infinity, list: [0x10, 'x',],}"#;
+        let expected = serde_json::json!({"s": "say \"hi\"", "t": "it's", "d": "a \"b\"", "hex": 31, "plus": 1, "dot": 0.5, "list": [16, "x"]});
+        let result = JSONParser::parse(json, ParseOptions::default().dialect(crate::Dialect::Json5)).unwrap();
+        for raw_numbers in [false, true] {
+            let options = SerializeOptions::default().raw_numbers(raw_numbers);
+            let value = serialize_slice_with_options(&result.json, &options).unwrap();
+            for written in [value.to_json(), value.to_json_compact()] {
+                let reparsed: serde_json::Value = serde_json::from_str(&written).unwrap_or_else(|e| panic!("{}: {}", e, written));
+                assert_eq!(reparsed, expected, "raw numbers {}", raw_numbers);
+            }
+            let mut converted = serde_json::Value::try_from(value).unwrap();
+            converted.as_object_mut().unwrap().retain(|_, v| !v.is_null());
+            assert_eq!(converted, expected, "raw numbers {}", raw_numbers);
+        }
+
+        // Raw data is parsed with Json5, a superset of Jsonc
+        for (dialect, json) in [(crate::Dialect::Json5, "{a: {b: 0x10, /* c */ c: [1,],},}"), (crate::Dialect::Jsonc, "{\"a\": {\"b\": 16, // c\n \"c\": [1,],}}")] {
+            let result = JSONParser::parse(json, ParseOptions::default().dialect(dialect).max_depth(1)).unwrap();
+            let converted = serde_json::Value::try_from(serialize_slice(&result.json).unwrap()).unwrap();
+            assert_eq!(converted, serde_json::json!({"a": {"b": 16, "c": [1]}}), "{:?}", dialect);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json_value_conversion() {
+        let json = r#"{"id": 1, "name": "a \"b\"", "ratio": 0.5, "none": null, "tags": ["x", 2, null], "nested": {"deep": {"ok": true, "missing": null}}, "empty": []}"#;
+        let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        // Raw parts are parsed during conversion
+        for max_depth in [1, 2, 10] {
+            let mut res = JSONParser::parse(json, ParseOptions::default().max_depth(max_depth)).unwrap().to_owned();
+            let value = serialize_to_json(&mut res.json).unwrap();
+            assert_eq!(serde_json::Value::try_from(value).unwrap(), expected, "max depth {}", max_depth);
+        }
+
+        let mut result = ParseResult::<String>::from(expected.clone());
+        let name = result.json.iter().find(|e| e.pointer.pointer.eq("/name")).unwrap();
+        assert_eq!((name.pointer.value_type, name.pointer.depth, name.value.as_deref()), (ValueType::String, 1, Some("a \\\"b\\\"")));
+        let tags = result.json.iter().find(|e| e.pointer.pointer.eq("/tags")).unwrap();
+        assert_eq!((tags.pointer.value_type, tags.pointer.depth), (ValueType::Array(3), 1));
+        let ok = result.json.iter().find(|e| e.pointer.pointer.eq("/nested/deep/ok")).unwrap();
+        assert_eq!((ok.pointer.value_type, ok.pointer.depth, ok.value.as_deref()), (ValueType::Bool, 3, Some("true")));
+        assert_eq!(result.max_json_depth, 3);
+        assert_eq!(result.json.iter().enumerate().filter(|(i, e)| e.pointer.position != *i).count(), 0);
+        assert_eq!(serde_json::Value::try_from(serialize_to_json(&mut result.json).unwrap()).unwrap(), expected);
+
+        let rows = ParseResult::<String>::from(serde_json::json!([{"a": 1}, 2]));
+        assert_eq!(rows.json.iter().map(|e| (e.pointer.pointer.as_str(), e.pointer.depth)).collect::<Vec<(&str, u8)>>(),
+                   vec![("", 1), ("/0", 1), ("/0/a", 2), ("/1", 1)]);
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -1515,9 +2081,122 @@ impl<V: serde::ser::Serialize + AsRef<str>> serde::ser::Serialize for Value<V> {
             },
             Value::Number(n) => serializer.serialize_f64(*n),
             Value::Integer(n) => serializer.serialize_i64(*n),
+            // Exact with the `arbitrary_precision` feature, otherwise serialized as i64, u64 or f64
+            Value::RawNumber(n) => match json5_number_to_json(n.as_ref()) {
+                Some(n) => serde_json::Number::from_str(&n).map_err(serde::ser::Error::custom)?.serialize(serializer),
+                None => serializer.serialize_unit(),
+            },
             Value::String(v) => v.serialize(serializer),
             Value::Bool(b) => serializer.serialize_bool(*b),
             Value::Null => serializer.serialize_unit(),
         }
     }
 }
+
+/// JSON text of `raw` written in `dialect`, e.g to hand raw data of a Json5 document to serde_json
+#[cfg(feature = "serde")]
+pub(crate) fn raw_to_json(raw: &str, dialect: crate::Dialect) -> Result<String, String> {
+    let mut result = crate::JSONParser::parse(raw, ParseOptions::default().max_depth(u8::MAX).dialect(dialect))?;
+    let value = _serialize_to_json(&mut result.json, &SerializeOptions::default().raw_numbers(true))?;
+    Ok(value._to_json_compact(true))
+}
+
+#[cfg(feature = "serde")]
+impl<V: AsRef<str>> TryFrom<Value<V>> for serde_json::Value {
+    type Error = String;
+
+    /// Raw parts (ObjectSerialized, ArraySerialized) are parsed during the conversion
+    fn try_from(value: Value<V>) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Object(map) => {
+                let mut object = serde_json::Map::with_capacity(map.len());
+                for (k, v) in map {
+                    object.insert(k, serde_json::Value::try_from(v)?);
+                }
+                serde_json::Value::Object(object)
+            }
+            // Raw data of a Jsonc or Json5 document is parsed as Json5, a superset of both
+            Value::ObjectSerialized(raw) | Value::ArraySerialized(raw) => match serde_json::from_str(raw.as_ref()) {
+                Ok(value) => value,
+                Err(e) => raw_to_json(raw.as_ref(), crate::Dialect::Json5).ok().and_then(|json| serde_json::from_str(&json).ok())
+                    .ok_or_else(|| format!("Can't parse raw value {}: {}", raw.as_ref(), e))?,
+            },
+            Value::Array(vec) => serde_json::Value::Array(vec.into_iter().map(serde_json::Value::try_from).collect::<Result<Vec<_>, String>>()?),
+            Value::Number(n) => serde_json::Number::from_f64(n).map(serde_json::Value::Number).ok_or_else(|| format!("Can't convert {} to a JSON number", n))?,
+            Value::Integer(n) => serde_json::Value::from(n),
+            Value::RawNumber(n) => match json5_number_to_json(n.as_ref()) {
+                Some(number) => serde_json::Number::from_str(&number).map(serde_json::Value::Number).map_err(|e| format!("Can't convert {} to a JSON number: {}", n.as_ref(), e))?,
+                None => serde_json::Value::Null,
+            },
+            Value::String(s) => serde_json::Value::String(crate::strings::unescape(s.as_ref()).into_owned()),
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::Null => serde_json::Value::Null,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Value> for ParseResult<String> {
+    /// Flatten `value` the same way the parser does with default options, without keeping raw data of objects.
+    fn from(value: serde_json::Value) -> Self {
+        let mut json: Vec<FlatJsonValue<String>> = Vec::with_capacity(64);
+        let mut max_json_depth = 0;
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    flatten_serde_value(concat_string!("/", key), value, 1, &mut json, &mut max_json_depth);
+                }
+            }
+            // Root array elements have the same depth as root array
+            serde_json::Value::Array(vec) => {
+                json.push(FlatJsonValue { pointer: PointerKey::from_pointer(String::new(), ValueType::Array(vec.len()), 1, 0), value: None });
+                for (i, value) in vec.into_iter().enumerate() {
+                    flatten_serde_value(concat_string!("/", i.to_string()), value, 1, &mut json, &mut max_json_depth);
+                }
+            }
+            value => flatten_serde_value(String::new(), value, 1, &mut json, &mut max_json_depth),
+        }
+        for (position, entry) in json.iter_mut().enumerate() {
+            entry.pointer.position = position;
+        }
+        ParseResult {
+            json,
+            max_json_depth,
+            parsing_max_depth: u8::try_from(max_json_depth).unwrap_or(u8::MAX),
+            started_parsing_at: None,
+            started_parsing_at_index_start: 0,
+            started_parsing_at_index_end: 0,
+            parsing_prefix: None,
+            depth_after_start_at: 0,
+            dialect: crate::Dialect::Json,
+            parse_array: true,
+            keep_object_raw_data: false,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn flatten_serde_value(pointer: String, value: serde_json::Value, depth: u8, json: &mut Vec<FlatJsonValue<String>>, max_json_depth: &mut usize) {
+    *max_json_depth = (*max_json_depth).max(depth as usize);
+    let (value_type, flat_value) = match value {
+        serde_json::Value::Object(map) => {
+            json.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer.clone(), ValueType::Object(true, map.len()), depth, 0), value: None });
+            for (key, value) in map {
+                flatten_serde_value(concat_string!(pointer, "/", key), value, depth + 1, json, max_json_depth);
+            }
+            return;
+        }
+        serde_json::Value::Array(vec) => {
+            json.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer.clone(), ValueType::Array(vec.len()), depth, 0), value: None });
+            for (i, value) in vec.into_iter().enumerate() {
+                flatten_serde_value(concat_string!(pointer, "/", i.to_string()), value, depth + 1, json, max_json_depth);
+            }
+            return;
+        }
+        serde_json::Value::Number(n) => (ValueType::Number, Some(n.to_string())),
+        serde_json::Value::String(s) => (ValueType::String, Some(crate::strings::escape(&s).into_owned())),
+        serde_json::Value::Bool(b) => (ValueType::Bool, Some(b.to_string())),
+        serde_json::Value::Null => (ValueType::Null, None),
+    };
+    json.push(FlatJsonValue { pointer: PointerKey::from_pointer(pointer, value_type, depth, 0), value: flat_value });
+}
@@ -0,0 +1,359 @@
+use std::fmt::Debug;
+use std::io;
+use std::io::{Read, Write};
+use std::ops::Range;
+
+use crate::{Comments, Dialect, FlatJsonValue, GetBytes, ParseResult, PointerKey, ValueType};
+
+const MAGIC: &[u8; 4] = b"JFPS";
+pub const SNAPSHOT_VERSION: u8 = 1;
+
+impl<V: Debug + Clone + AsRef<str> + GetBytes> ParseResult<V> {
+    /// Write a compact binary snapshot of this result, it can be loaded back with `ParseResult::read_snapshot`.
+    /// Pointers are prefix-compressed: each one only stores what differs from the previous one.
+    pub fn write_snapshot(&self, mut writer: impl Write) -> io::Result<()> {
+        let mut out: Vec<u8> = Vec::with_capacity(self.json.len() * 16 + 64);
+        out.extend_from_slice(MAGIC);
+        out.push(SNAPSHOT_VERSION);
+        write_varint(&mut out, self.max_json_depth);
+        out.push(self.parsing_max_depth);
+        write_optional_str(&mut out, self.started_parsing_at.as_deref());
+        write_varint(&mut out, self.started_parsing_at_index_start);
+        write_varint(&mut out, self.started_parsing_at_index_end);
+        write_optional_str(&mut out, self.parsing_prefix.as_deref());
+        out.push(self.depth_after_start_at);
+        out.push(match self.dialect {
+            Dialect::Json => 0,
+            Dialect::Jsonc => 1,
+            Dialect::Json5 => 2,
+        });
+        out.push(self.parse_array as u8 | (self.keep_object_raw_data as u8) << 1);
+        write_varint(&mut out, self.json.len());
+
+        let mut previous = "";
+        for entry in self.json.iter() {
+            let pointer = entry.pointer.pointer.as_str();
+            let mut shared = pointer.bytes().zip(previous.bytes()).take_while(|(a, b)| a == b).count();
+            while !pointer.is_char_boundary(shared) {
+                shared -= 1;
+            }
+            write_varint(&mut out, shared);
+            write_str(&mut out, &pointer[shared..]);
+            match entry.pointer.value_type {
+                ValueType::Array(len) => {
+                    out.push(0);
+                    write_varint(&mut out, len);
+                }
+                ValueType::Object(parsed, len) => {
+                    out.push(if parsed { 2 } else { 1 });
+                    write_varint(&mut out, len);
+                }
+                ValueType::Number => out.push(3),
+                ValueType::String => out.push(4),
+                ValueType::Bool => out.push(5),
+                ValueType::Null => out.push(6),
+                ValueType::None => out.push(7),
+            }
+            out.push(entry.pointer.depth);
+            write_varint(&mut out, entry.pointer.position);
+            write_varint(&mut out, entry.pointer.column_id);
+            write_optional_span(&mut out, entry.pointer.span.as_ref())?;
+            write_optional_span(&mut out, entry.pointer.key_span.as_ref())?;
+            match entry.pointer.comments {
+                Some(ref comments) => {
+                    out.push(1);
+                    write_strs(&mut out, &comments.leading);
+                    write_strs(&mut out, &comments.trailing);
+                }
+                None => out.push(0),
+            }
+            write_optional_str(&mut out, entry.value.as_ref().map(|v| v.as_ref()));
+            previous = pointer;
+        }
+        writer.write_all(&out)?;
+        writer.flush()
+    }
+}
+
+impl ParseResult<String> {
+    /// Load a snapshot written by `write_snapshot`. Snapshots written by another version of the format are rejected.
+    pub fn read_snapshot(mut reader: impl Read) -> io::Result<Self> {
+        let mut data: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut input = SnapshotReader { data: &data, index: 0 };
+        if input.bytes(MAGIC.len())? != MAGIC {
+            return Err(invalid_data("Not a snapshot".to_string()));
+        }
+        let version = input.u8()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(invalid_data(format!("Unsupported snapshot version {}, expected {}", version, SNAPSHOT_VERSION)));
+        }
+        let max_json_depth = input.varint()?;
+        let parsing_max_depth = input.u8()?;
+        let started_parsing_at = input.optional_string()?;
+        let started_parsing_at_index_start = input.varint()?;
+        let started_parsing_at_index_end = input.varint()?;
+        let parsing_prefix = input.optional_string()?;
+        let depth_after_start_at = input.u8()?;
+        let dialect = match input.u8()? {
+            0 => Dialect::Json,
+            1 => Dialect::Jsonc,
+            2 => Dialect::Json5,
+            tag => return Err(invalid_data(format!("Invalid dialect {}", tag))),
+        };
+        let flags = input.u8()?;
+        if flags > 3 {
+            return Err(invalid_data(format!("Invalid parse options {}", flags)));
+        }
+        let len = input.varint()?;
+
+        let mut json: Vec<FlatJsonValue<String>> = Vec::with_capacity(len.min(data.len()));
+        let mut previous = String::new();
+        for _ in 0..len {
+            let shared = input.varint()?;
+            if shared > previous.len() || !previous.is_char_boundary(shared) {
+                return Err(invalid_data("Invalid pointer prefix".to_string()));
+            }
+            let mut pointer = String::with_capacity(shared + 16);
+            pointer.push_str(&previous[..shared]);
+            pointer.push_str(input.str()?);
+            let value_type = match input.u8()? {
+                0 => ValueType::Array(input.varint()?),
+                1 => ValueType::Object(false, input.varint()?),
+                2 => ValueType::Object(true, input.varint()?),
+                3 => ValueType::Number,
+                4 => ValueType::String,
+                5 => ValueType::Bool,
+                6 => ValueType::Null,
+                7 => ValueType::None,
+                tag => return Err(invalid_data(format!("Invalid value type {}", tag))),
+            };
+            let depth = input.u8()?;
+            let position = input.varint()?;
+            let column_id = input.varint()?;
+            let span = input.optional_span()?;
+            let key_span = input.optional_span()?;
+            let comments = match input.u8()? {
+                0 => None,
+                1 => Some(Box::new(Comments { leading: input.strings()?, trailing: input.strings()? })),
+                tag => return Err(invalid_data(format!("Invalid option tag {}", tag))),
+            };
+            let value = input.optional_string()?;
+            previous.clone_from(&pointer);
+            json.push(FlatJsonValue { pointer: PointerKey { pointer, value_type, depth, position, column_id, span, key_span, comments }, value });
+        }
+        if input.index != data.len() {
+            return Err(invalid_data("Unexpected data after snapshot".to_string()));
+        }
+
+        Ok(ParseResult {
+            json,
+            max_json_depth,
+            parsing_max_depth,
+            started_parsing_at,
+            started_parsing_at_index_start,
+            started_parsing_at_index_end,
+            parsing_prefix,
+            depth_after_start_at,
+            dialect,
+            parse_array: flags & 1 != 0,
+            keep_object_raw_data: flags & 2 != 0,
+        })
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// LEB128
+fn write_varint(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_varint(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_optional_str(out: &mut Vec<u8>, s: Option<&str>) {
+    match s {
+        Some(s) => {
+            out.push(1);
+            write_str(out, s);
+        }
+        None => out.push(0),
+    }
+}
+
+fn write_strs(out: &mut Vec<u8>, strs: &[String]) {
+    write_varint(out, strs.len());
+    for s in strs {
+        write_str(out, s);
+    }
+}
+
+// span is stored as start and length
+fn write_optional_span(out: &mut Vec<u8>, span: Option<&Range<usize>>) -> io::Result<()> {
+    match span {
+        Some(span) => {
+            let len = span.end.checked_sub(span.start)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid span {:?}", span)))?;
+            out.push(1);
+            write_varint(out, span.start);
+            write_varint(out, len);
+        }
+        None => out.push(0),
+    }
+    Ok(())
+}
+
+struct SnapshotReader<'a> {
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if len > self.data.len() - self.index {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated snapshot"));
+        }
+        let bytes = &self.data[self.index..self.index + len];
+        self.index += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn varint(&mut self) -> io::Result<usize> {
+        let mut n: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift >= usize::BITS {
+                return Err(invalid_data("Invalid varint".to_string()));
+            }
+            n |= ((byte & 0x7F) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+
+    fn str(&mut self) -> io::Result<&'a str> {
+        let len = self.varint()?;
+        std::str::from_utf8(self.bytes(len)?).map_err(|e| invalid_data(e.to_string()))
+    }
+
+    fn optional_string(&mut self) -> io::Result<Option<String>> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.str()?.to_string())),
+            tag => Err(invalid_data(format!("Invalid option tag {}", tag))),
+        }
+    }
+
+    fn strings(&mut self) -> io::Result<Vec<String>> {
+        let len = self.varint()?;
+        (0..len).map(|_| self.str().map(|s| s.to_string())).collect()
+    }
+
+    fn optional_span(&mut self) -> io::Result<Option<Range<usize>>> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => {
+                let start = self.varint()?;
+                let end = start.checked_add(self.varint()?).ok_or_else(|| invalid_data("Invalid span".to_string()))?;
+                Ok(Some(start..end))
+            }
+            tag => Err(invalid_data(format!("Invalid option tag {}", tag))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::ops::Range;
+
+    use crate::{Dialect, JSONParser, ParseOptions, ParseResult};
+    use crate::snapshot::SNAPSHOT_VERSION;
+
+    fn assert_same_result(actual: &ParseResult<String>, expected: &ParseResult<String>) {
+        assert_eq!(actual.json.len(), expected.json.len());
+        for (a, e) in actual.json.iter().zip(expected.json.iter()) {
+            assert_eq!(a.pointer.pointer, e.pointer.pointer);
+            assert_eq!(a.pointer.value_type, e.pointer.value_type);
+            assert_eq!(a.pointer.depth, e.pointer.depth);
+            assert_eq!(a.pointer.position, e.pointer.position);
+            assert_eq!(a.pointer.column_id, e.pointer.column_id);
+            assert_eq!(a.pointer.span, e.pointer.span);
+            assert_eq!(a.pointer.key_span, e.pointer.key_span);
+            assert_eq!(a.pointer.comments, e.pointer.comments);
+            assert_eq!(a.value, e.value);
+        }
+        assert_eq!(actual.max_json_depth, expected.max_json_depth);
+        assert_eq!(actual.parsing_max_depth, expected.parsing_max_depth);
+        assert_eq!(actual.started_parsing_at, expected.started_parsing_at);
+        assert_eq!(actual.started_parsing_at_index_start, expected.started_parsing_at_index_start);
+        assert_eq!(actual.started_parsing_at_index_end, expected.started_parsing_at_index_end);
+        assert_eq!(actual.parsing_prefix, expected.parsing_prefix);
+        assert_eq!(actual.depth_after_start_at, expected.depth_after_start_at);
+        assert_eq!(actual.dialect, expected.dialect);
+        assert_eq!((actual.parse_array, actual.keep_object_raw_data), (expected.parse_array, expected.keep_object_raw_data));
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let json = fs::read_to_string("examples/grafana.json").unwrap();
+        for options in [ParseOptions::default(), ParseOptions::default().max_depth(2).parse_array(false),
+                        ParseOptions::default().start_parse_at("/panels".to_string()).parse_array(false)] {
+            let mut result = JSONParser::parse(json.as_str(), options).unwrap().to_owned();
+            result.json[1].pointer.column_id = 300;
+            let mut snapshot: Vec<u8> = Vec::new();
+            result.write_snapshot(&mut snapshot).unwrap();
+            let loaded = ParseResult::read_snapshot(snapshot.as_slice()).unwrap();
+            assert_same_result(&loaded, &result);
+        }
+
+        let result = JSONParser::parse(r#"{"é": {"éa": "x", "éb": null}}"#, ParseOptions::default()).unwrap();
+        let mut snapshot: Vec<u8> = Vec::new();
+        result.write_snapshot(&mut snapshot).unwrap();
+        assert_same_result(&ParseResult::read_snapshot(snapshot.as_slice()).unwrap(), &result.to_owned());
+
+        let result = JSONParser::parse("{\n  // a\n  \"a\": 1, /* b */ b: 2\n}", ParseOptions::default().dialect(Dialect::Json5)).unwrap();
+        let mut snapshot: Vec<u8> = Vec::new();
+        result.write_snapshot(&mut snapshot).unwrap();
+        assert_same_result(&ParseResult::read_snapshot(snapshot.as_slice()).unwrap(), &result.to_owned());
+    }
+
+    #[test]
+    fn snapshot_rejects_invalid_data() {
+        let result = JSONParser::parse(r#"{"a": [1, 2]}"#, ParseOptions::default()).unwrap();
+        let mut snapshot: Vec<u8> = Vec::new();
+        result.write_snapshot(&mut snapshot).unwrap();
+
+        let mut other_version = snapshot.clone();
+        other_version[4] = SNAPSHOT_VERSION + 1;
+        let error = ParseResult::read_snapshot(other_version.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), format!("Unsupported snapshot version {}, expected {}", SNAPSHOT_VERSION + 1, SNAPSHOT_VERSION));
+
+        let error = ParseResult::read_snapshot(&snapshot[..snapshot.len() - 1]).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert!(ParseResult::read_snapshot(&b"{\"a\": 1}"[..]).is_err());
+
+        let mut result = result;
+        result.json[0].pointer.span = Some(Range { start: 5, end: 2 });
+        let error = result.write_snapshot(&mut Vec::new()).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,142 @@
+//! Helpers for string values, which the parser keeps JSON escaped.
+
+use std::borrow::Cow;
+
+/// Strings are kept escaped by the parser, this gives their actual value
+pub(crate) fn unescape(value: &str) -> Cow<'_, str> {
+    if !value.contains('\\') {
+        return Cow::Borrowed(value);
+    }
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('b') => unescaped.push('\u{8}'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some('u') => {
+                let code = chars.by_ref().take(4).collect::<String>();
+                let mut code = u32::from_str_radix(&code, 16).unwrap_or(0xFFFD);
+                if (0xD800..0xDC00).contains(&code) && chars.as_str().starts_with("\\u") {
+                    let low = chars.as_str().get(2..6).and_then(|s| u32::from_str_radix(s, 16).ok()).unwrap_or(0);
+                    if (0xDC00..0xE000).contains(&low) {
+                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        chars.by_ref().take(6).for_each(drop);
+                    }
+                }
+                unescaped.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+            }
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Reverse of `unescape`, strings are stored escaped like the parser does
+pub(crate) fn escape(value: &str) -> Cow<'_, str> {
+    if !value.chars().any(|c| c == '"' || c == '\\' || c.is_control()) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Json5 string content as JSON string content: `\'` is unescaped and bare `"` of single-quoted strings are escaped.
+/// JSON strings are returned as is.
+pub(crate) fn json5_string_to_json(value: &str) -> Cow<'_, str> {
+    if !value.contains('"') && !value.contains("\\'") {
+        return Cow::Borrowed(value);
+    }
+    let mut json = String::with_capacity(value.len() + 8);
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\'') => json.push('\''),
+                Some(c) => {
+                    json.push('\\');
+                    json.push(c);
+                }
+                None => json.push_str("\\\\"),
+            },
+            '"' => json.push_str("\\\""),
+            c => json.push(c),
+        }
+    }
+    Cow::Owned(json)
+}
+
+/// Json5 number as a JSON number: hexadecimal is converted to decimal, leading `+` is dropped and `.5` or `5.` get their missing zero.
+/// None for `NaN` and `Infinity` which JSON can't represent. JSON numbers are returned as is.
+pub(crate) fn json5_number_to_json(value: &str) -> Option<Cow<'_, str>> {
+    let (sign, digits) = match value.as_bytes().first()? {
+        b'-' => ("-", &value[1..]),
+        b'+' => ("", &value[1..]),
+        _ => ("", value),
+    };
+    if digits == "Infinity" || digits == "NaN" {
+        return None;
+    }
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        let n = u128::from_str_radix(hex, 16).ok()?;
+        return Some(Cow::Owned(if n == 0 { "0".to_string() } else { format!("{}{}", sign, n) }));
+    }
+    let missing_zero = digits.starts_with('.') || digits.contains(".e") || digits.contains(".E") || digits.ends_with('.');
+    if !missing_zero && !value.starts_with('+') {
+        return Some(Cow::Borrowed(value));
+    }
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push_str(sign);
+    if digits.starts_with('.') {
+        json.push('0');
+    }
+    let mut chars = digits.chars().peekable();
+    while let Some(c) = chars.next() {
+        json.push(c);
+        if c == '.' && !chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+            json.push('0');
+        }
+    }
+    Some(Cow::Owned(json))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strings::{escape, json5_number_to_json, json5_string_to_json, unescape};
+
+    #[test]
+    fn escape_unescape() {
+        assert_eq!(unescape(r#"a\\b\/c\td"#), "a\\b/c\td");
+        assert_eq!(unescape(r#"\ud83d\ude00"#), "😀");
+        assert_eq!(escape("a\"b\\c\n"), r#"a\"b\\c\n"#);
+        assert_eq!(unescape(&escape("é\u{1}\t")), "é\u{1}\t");
+    }
+
+    #[test]
+    fn json5_to_json() {
+        assert_eq!(json5_string_to_json(r#"a\"b\\"#), r#"a\"b\\"#);
+        assert_eq!(json5_string_to_json(r#"it\'s "quoted""#), r#"it's \"quoted\""#);
+        let numbers = ["1", "-1.5e3", "0x1F", "-0x1f", "+1", ".5", "-.5", "5.", "5.e2", "0x0"].map(|n| json5_number_to_json(n).unwrap().into_owned());
+        assert_eq!(numbers, ["1", "-1.5e3", "31", "-31", "1", "0.5", "-0.5", "5.0", "5.0e2", "0"]);
+        assert_eq!(json5_number_to_json("NaN"), None);
+        assert_eq!(json5_number_to_json("-Infinity"), None);
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::{concat_string, FlatJsonValue, GetBytes, ParseResult, ValueType};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub id: usize,
+    pub name: String,    // nested keys joined with '.', for display: keys may contain '.' as well
+    pub pointer: String, // pointer relative to the row, e.g: /user/name
+}
+
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub index: usize, // index of the array element entry in ParseResult::json
+    pub pointer: String,
+    cells: Vec<Option<usize>>,
+}
+
+impl Row {
+    /// Index in ParseResult::json of the cell at `column_id`
+    pub fn cell(&self, column_id: usize) -> Option<usize> {
+        self.cells.get(column_id).copied().flatten()
+    }
+
+    pub fn cells(&self) -> &[Option<usize>] {
+        &self.cells
+    }
+}
+
+/// Rows and columns view of an array of objects.
+/// Columns are the union of keys across array elements, nested objects and arrays are flattened up to the parsed depth.
+/// Cells are indexes of entries in ParseResult::json, so a view is only valid as long as the result is not modified.
+#[derive(Debug, Clone)]
+pub struct TableView {
+    pub array_pointer: String,
+    columns: Vec<Column>,
+    rows: Vec<Row>,
+}
+
+impl TableView {
+    pub fn from<V: Debug + Clone + AsRef<str> + GetBytes>(result: &ParseResult<V>, array_pointer: &str) -> Result<Self, String> {
+        let Some(array) = result.json.iter().find(|e| e.pointer.pointer.eq(array_pointer)) else {
+            return Err(format!("No entry found at {}", array_pointer));
+        };
+        if !matches!(array.pointer.value_type, ValueType::Array(_)) {
+            return Err(format!("Entry at {} is not an array", array_pointer));
+        }
+        let prefix = concat_string!(array_pointer, "/");
+
+        let mut rows: Vec<Row> = vec![];
+        let mut row_by_element: HashMap<usize, usize> = HashMap::new();
+        // (row, column name, relative pointer, entry index)
+        let mut candidates: Vec<(usize, String, &str, usize)> = vec![];
+        for (i, entry) in result.json.iter().enumerate() {
+            let Some(rest) = entry.pointer.pointer.strip_prefix(prefix.as_str()) else {
+                continue;
+            };
+            let (element, relative_pointer) = match rest.find('/') {
+                Some(slash) => (&rest[..slash], &rest[slash..]),
+                None => (rest, ""),
+            };
+            let Ok(element) = usize::from_str(element) else {
+                continue;
+            };
+            let row = *row_by_element.entry(element).or_insert_with(|| {
+                rows.push(Row { index: i, pointer: concat_string!(prefix, element.to_string()), cells: vec![] });
+                rows.len() - 1
+            });
+            if relative_pointer.is_empty() {
+                rows[row].index = i;
+            } else {
+                candidates.push((row, relative_pointer[1..].replace('/', "."), relative_pointer, i));
+            }
+        }
+        if rows.is_empty() && array.value.is_some() {
+            return Err(format!("Array at {} has not been parsed", array_pointer));
+        }
+
+        // Containers whose children have been parsed are not cells, their children are.
+        // Parents and columns are found by pointer, a key containing '.' is not a nested key.
+        let parents: HashSet<(usize, &str)> = candidates.iter()
+            .flat_map(|(row, _, relative_pointer, _)| relative_pointer.match_indices('/').skip(1).map(move |(slash, _)| (*row, &relative_pointer[..slash])))
+            .collect();
+
+        let mut columns: Vec<Column> = vec![];
+        let mut column_by_pointer: HashMap<&str, usize> = HashMap::new();
+        for (row, name, relative_pointer, i) in candidates.iter() {
+            if parents.contains(&(*row, *relative_pointer)) {
+                continue;
+            }
+            let column_id = *column_by_pointer.entry(relative_pointer).or_insert_with(|| {
+                columns.push(Column { id: columns.len(), name: name.clone(), pointer: relative_pointer.to_string() });
+                columns.len() - 1
+            });
+            let cells = &mut rows[*row].cells;
+            if cells.len() <= column_id {
+                cells.resize(column_id + 1, None);
+            }
+            cells[column_id] = Some(*i);
+        }
+        for row in rows.iter_mut() {
+            row.cells.resize(columns.len(), None);
+        }
+
+        Ok(Self { array_pointer: array_pointer.to_string(), columns, rows })
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// First column named `name`, columns at different pointers may have the same name
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name.eq(name))
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    pub fn rows_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Index in ParseResult::json of the cell at `row`, `column_id`
+    pub fn cell(&self, row: usize, column_id: usize) -> Option<usize> {
+        self.rows.get(row).and_then(|r| r.cell(column_id))
+    }
+
+    pub fn cell_value<'a, V: Debug + Clone + AsRef<str> + GetBytes>(&self, result: &'a ParseResult<V>, row: usize, column_id: usize) -> Option<&'a FlatJsonValue<V>> {
+        self.cell(row, column_id).and_then(|i| result.json.get(i))
+    }
+
+    /// Set `PointerKey::column_id` of each cell entry of `result`.
+    pub fn assign_column_ids<V: Debug + Clone + AsRef<str> + GetBytes>(&self, result: &mut ParseResult<V>) {
+        for row in self.rows.iter() {
+            for (column_id, cell) in row.cells.iter().enumerate() {
+                if let Some(entry) = cell.and_then(|i| result.json.get_mut(i)) {
+                    entry.pointer.column_id = column_id;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{JSONParser, ParseOptions};
+    use crate::table::TableView;
+
+    #[test]
+    fn table_from_root_array() {
+        let json = r#"[{"id": 1, "user": {"name": "a", "age": 10}}, {"id": 2, "tags": ["x", "y"]}, {"user": {"name": "c"}, "extra": null}]"#;
+        let mut result = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let table = TableView::from(&result, "").unwrap();
+
+        let names = table.columns().iter().map(|c| c.name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(names, vec!["id", "user.name", "user.age", "tags.0", "tags.1", "extra"]);
+        assert_eq!(table.column("user.name").unwrap().pointer, "/user/name");
+        assert_eq!(table.rows_count(), 3);
+        assert_eq!(table.rows()[1].pointer, "/1");
+
+        let user_name = table.column("user.name").unwrap().id;
+        assert_eq!(table.cell_value(&result, 0, user_name).unwrap().value, Some("a"));
+        assert_eq!(table.cell_value(&result, 2, user_name).unwrap().value, Some("c"));
+        assert!(table.cell(1, user_name).is_none());
+        assert_eq!(table.cell_value(&result, 1, table.column("tags.1").unwrap().id).unwrap().value, Some("y"));
+        assert_eq!(table.cell_value(&result, 2, table.column("extra").unwrap().id).unwrap().pointer.pointer, "/2/extra");
+
+        table.assign_column_ids(&mut result);
+        assert_eq!(result.json.iter().find(|e| e.pointer.pointer.eq("/2/user/name")).unwrap().pointer.column_id, user_name);
+    }
+
+    #[test]
+    fn table_keys_with_dots() {
+        let json = r#"[{"a": 1, "a.b": 2}, {"a": {"b": 3}}]"#;
+        let result = JSONParser::parse(json, ParseOptions::default()).unwrap();
+        let table = TableView::from(&result, "").unwrap();
+        let columns = table.columns().iter().map(|c| (c.name.as_str(), c.pointer.as_str())).collect::<Vec<(&str, &str)>>();
+        assert_eq!(columns, vec![("a", "/a"), ("a.b", "/a.b"), ("a.b", "/a/b")]);
+        assert_eq!(table.cell_value(&result, 0, 0).unwrap().value, Some("1"));
+        assert!(table.cell(1, 0).is_none());
+        assert_eq!(table.cell_value(&result, 0, 1).unwrap().value, Some("2"));
+        assert_eq!(table.cell_value(&result, 1, 2).unwrap().value, Some("3"));
+    }
+
+    #[test]
+    fn table_up_to_parsed_depth() {
+        let json = r#"{"data": [{"id": 1, "user": {"name": "a"}}, {"id": 2, "user": {"name": "b"}}]}"#;
+        let result = JSONParser::parse(json, ParseOptions::default().max_depth(3)).unwrap();
+        let table = TableView::from(&result, "/data").unwrap();
+        let names = table.columns().iter().map(|c| c.name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(names, vec!["id", "user"]);
+        assert_eq!(table.cell_value(&result, 1, 1).unwrap().value, Some(r#"{"name": "b"}"#));
+
+        assert!(TableView::from(&result, "/data/0").is_err());
+        assert!(TableView::from(&result, "/missing").is_err());
+        let result = JSONParser::parse(json, ParseOptions::default().parse_array(false)).unwrap();
+        assert!(TableView::from(&result, "/data").is_err());
+    }
+}
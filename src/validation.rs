@@ -0,0 +1,481 @@
+#[cfg(feature = "regex")]
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use crate::{concat_string, has_children, Dialect, FlatJsonValue, GetBytes, JSONParser, ParseOptions, ParseResult, ValueType};
+use crate::strings::{json5_number_to_json, unescape};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub pointer: String, // pointer of the invalid value
+    pub keyword: String,
+    pub message: String,
+}
+
+// Document tree built from flat entries
+#[derive(Debug)]
+enum Instance<'a> {
+    Null,
+    Bool(bool),
+    Number(&'a str),
+    String(&'a str), // escaped, as stored by the parser
+    Array(Vec<Instance<'a>>),
+    Object(Vec<(String, Instance<'a>)>),
+    Raw(&'a str, bool, Dialect), // object or array (true) not parsed, parsed lazily with the dialect of the document when validation needs its content
+}
+
+/// Keywords which can be checked on an object or array without looking at its content
+const ANNOTATION_KEYWORDS: [&str; 10] = ["type", "title", "description", "$schema", "$id", "$comment", "$defs", "definitions", "examples", "default"];
+const MAX_REF_DEPTH: usize = 64;
+
+/// JSON Schema supporting: type, required, properties, additionalProperties, enum, const, minimum/maximum (and exclusive ones),
+/// minLength/maxLength, minItems/maxItems, pattern (with "regex" feature), items, prefixItems, oneOf/anyOf/allOf/not and local $ref.
+pub struct JsonSchema<'a> {
+    root: Instance<'a>,
+    #[cfg(feature = "regex")]
+    patterns: HashMap<String, regex_lite::Regex>,
+}
+
+impl<'a> JsonSchema<'a> {
+    pub fn parse(schema: &'a str) -> Result<Self, String> {
+        let root = parse_instance(schema, Dialect::Json)?;
+        let mut patterns = vec![];
+        collect_patterns(&root, &mut patterns);
+        #[cfg(feature = "regex")]
+        {
+            let mut compiled = HashMap::new();
+            for pattern in patterns {
+                let regex = regex_lite::Regex::new(&pattern).map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?;
+                compiled.insert(pattern, regex);
+            }
+            Ok(Self { root, patterns: compiled })
+        }
+        #[cfg(not(feature = "regex"))]
+        {
+            if let Some(pattern) = patterns.first() {
+                return Err(format!("Pattern {} can't be checked, \"regex\" feature is required", pattern));
+            }
+            Ok(Self { root })
+        }
+    }
+
+    /// Validate `result`, returned errors are located by pointer of the invalid value.
+    pub fn validate<V: Debug + Clone + AsRef<str> + GetBytes>(&self, result: &ParseResult<V>) -> Vec<ValidationError> {
+        let instance = build_root(&result.json, result.dialect, &|e| e.value.as_ref().map(|v| v.as_ref()));
+        let mut errors = vec![];
+        self.validate_node(&instance, &self.root, "", &mut errors, 0);
+        errors
+    }
+
+    fn is_valid(&self, node: &Instance, schema: &Instance, ref_depth: usize) -> bool {
+        let mut errors = vec![];
+        self.validate_node(node, schema, "", &mut errors, ref_depth);
+        errors.is_empty()
+    }
+
+    fn validate_node(&self, node: &Instance, schema: &Instance, pointer: &str, errors: &mut Vec<ValidationError>, ref_depth: usize) {
+        let keywords = match schema {
+            Instance::Bool(false) => {
+                errors.push(error(pointer, "false", "No value is allowed".to_string()));
+                return;
+            }
+            Instance::Object(keywords) => keywords,
+            _ => return,
+        };
+
+        let expanded;
+        let node = match node {
+            Instance::Raw(raw, _, dialect) if keywords.iter().any(|(k, _)| !ANNOTATION_KEYWORDS.contains(&k.as_str())) => {
+                match parse_instance(raw, *dialect) {
+                    Ok(instance) => {
+                        expanded = instance;
+                        &expanded
+                    }
+                    Err(e) => {
+                        errors.push(error(pointer, "type", format!("Invalid JSON: {}", e)));
+                        return;
+                    }
+                }
+            }
+            _ => node,
+        };
+
+        for (keyword, value) in keywords.iter() {
+            match (keyword.as_str(), value) {
+                ("$ref", Instance::String(reference)) => {
+                    match self.resolve(reference) {
+                        Some(_) if ref_depth >= MAX_REF_DEPTH => errors.push(error(pointer, "$ref", format!("Too many nested $ref resolving {}", reference))),
+                        Some(target) => self.validate_node(node, target, pointer, errors, ref_depth + 1),
+                        None => errors.push(error(pointer, "$ref", format!("Can't resolve $ref {}", reference))),
+                    }
+                }
+                ("type", Instance::String(expected)) if !has_type(node, expected) => {
+                    errors.push(error(pointer, "type", format!("Expected type {}, found {}", expected, type_name(node))));
+                }
+                ("type", Instance::Array(expected)) if !expected.iter().any(|t| matches!(t, Instance::String(t) if has_type(node, t))) => {
+                    let expected = expected.iter().filter_map(|t| if let Instance::String(t) = t { Some(*t) } else { None }).collect::<Vec<&str>>();
+                    errors.push(error(pointer, "type", format!("Expected type {}, found {}", expected.join(" or "), type_name(node))));
+                }
+                ("enum", Instance::Array(values)) if !values.iter().any(|v| equals(node, v)) => {
+                    errors.push(error(pointer, "enum", "Value is not one of the allowed values".to_string()));
+                }
+                ("const", value) if !equals(node, value) => {
+                    errors.push(error(pointer, "const", "Value is not equal to the constant".to_string()));
+                }
+                ("minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum", Instance::Number(limit)) => {
+                    let (Instance::Number(n), Some(limit)) = (node, number(limit)) else {
+                        continue;
+                    };
+                    let n = number(n).unwrap_or(f64::NAN);
+                    let valid = match keyword.as_str() {
+                        "minimum" => n >= limit,
+                        "maximum" => n <= limit,
+                        "exclusiveMinimum" => n > limit,
+                        _ => n < limit,
+                    };
+                    if !valid {
+                        errors.push(error(pointer, keyword, format!("Value {} does not respect {} {}", n, keyword, limit)));
+                    }
+                }
+                ("minLength" | "maxLength", Instance::Number(limit)) => {
+                    let (Instance::String(s), Ok(limit)) = (node, limit.parse::<usize>()) else {
+                        continue;
+                    };
+                    let len = unescape(s).chars().count();
+                    if (keyword == "minLength" && len < limit) || (keyword == "maxLength" && len > limit) {
+                        errors.push(error(pointer, keyword, format!("String length {} does not respect {} {}", len, keyword, limit)));
+                    }
+                }
+                ("minItems" | "maxItems", Instance::Number(limit)) => {
+                    let (Instance::Array(items), Ok(limit)) = (node, limit.parse::<usize>()) else {
+                        continue;
+                    };
+                    if (keyword == "minItems" && items.len() < limit) || (keyword == "maxItems" && items.len() > limit) {
+                        errors.push(error(pointer, keyword, format!("Array length {} does not respect {} {}", items.len(), keyword, limit)));
+                    }
+                }
+                #[cfg(feature = "regex")]
+                ("pattern", Instance::String(pattern)) => {
+                    let (Instance::String(s), Some(regex)) = (node, self.patterns.get(unescape(pattern).as_ref())) else {
+                        continue;
+                    };
+                    if !regex.is_match(&unescape(s)) {
+                        errors.push(error(pointer, "pattern", format!("Value does not match pattern {}", pattern)));
+                    }
+                }
+                ("required", Instance::Array(required)) => {
+                    let Instance::Object(members) = node else {
+                        continue;
+                    };
+                    for key in required.iter() {
+                        if let Instance::String(key) = key {
+                            if !members.iter().any(|(k, _)| k.eq(key)) {
+                                errors.push(error(pointer, "required", format!("Missing required property {}", key)));
+                            }
+                        }
+                    }
+                }
+                ("properties", Instance::Object(properties)) => {
+                    let Instance::Object(members) = node else {
+                        continue;
+                    };
+                    for (key, member) in members.iter() {
+                        if let Some((_, property)) = properties.iter().find(|(k, _)| k.eq(key)) {
+                            self.validate_node(member, property, &concat_string!(pointer, "/", key), errors, 0);
+                        }
+                    }
+                }
+                ("additionalProperties", additional) => {
+                    let Instance::Object(members) = node else {
+                        continue;
+                    };
+                    let properties = keywords.iter().find(|(k, _)| k.eq("properties")).map(|(_, p)| p);
+                    for (key, member) in members.iter() {
+                        let is_declared = matches!(properties, Some(Instance::Object(properties)) if properties.iter().any(|(k, _)| k.eq(key)));
+                        if is_declared {
+                            continue;
+                        }
+                        let member_pointer = concat_string!(pointer, "/", key);
+                        if matches!(additional, Instance::Bool(false)) {
+                            errors.push(error(&member_pointer, "additionalProperties", format!("Additional property {} is not allowed", key)));
+                        } else {
+                            self.validate_node(member, additional, &member_pointer, errors, 0);
+                        }
+                    }
+                }
+                ("prefixItems", Instance::Array(prefix_items)) => {
+                    let Instance::Array(items) = node else {
+                        continue;
+                    };
+                    for (i, (item, item_schema)) in items.iter().zip(prefix_items.iter()).enumerate() {
+                        self.validate_node(item, item_schema, &concat_string!(pointer, "/", i.to_string()), errors, 0);
+                    }
+                }
+                ("items", item_schema) => {
+                    let Instance::Array(items) = node else {
+                        continue;
+                    };
+                    let skip = match keywords.iter().find(|(k, _)| k.eq("prefixItems")) {
+                        Some((_, Instance::Array(prefix_items))) => prefix_items.len(),
+                        _ => 0,
+                    };
+                    for (i, item) in items.iter().enumerate().skip(skip) {
+                        self.validate_node(item, item_schema, &concat_string!(pointer, "/", i.to_string()), errors, 0);
+                    }
+                }
+                ("allOf", Instance::Array(schemas)) => {
+                    for schema in schemas.iter() {
+                        self.validate_node(node, schema, pointer, errors, ref_depth);
+                    }
+                }
+                ("anyOf", Instance::Array(schemas)) if !schemas.iter().any(|schema| self.is_valid(node, schema, ref_depth)) => {
+                    errors.push(error(pointer, "anyOf", "Value does not match any schema".to_string()));
+                }
+                ("oneOf", Instance::Array(schemas)) => {
+                    let matching = schemas.iter().filter(|schema| self.is_valid(node, schema, ref_depth)).count();
+                    if matching != 1 {
+                        errors.push(error(pointer, "oneOf", format!("Value matches {} schemas, expected exactly one", matching)));
+                    }
+                }
+                ("not", schema) if self.is_valid(node, schema, ref_depth) => {
+                    errors.push(error(pointer, "not", "Value matches a schema it should not".to_string()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Resolve a local reference, e.g: "#" or "#/$defs/address"
+    fn resolve(&self, reference: &str) -> Option<&Instance<'a>> {
+        let pointer = reference.strip_prefix('#')?;
+        let mut node = &self.root;
+        for segment in pointer.split('/').skip(1) {
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            node = match node {
+                Instance::Object(members) => members.iter().find(|(k, _)| k.eq(&segment)).map(|(_, v)| v)?,
+                Instance::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+}
+
+impl<V: Debug + Clone + AsRef<str> + GetBytes> ParseResult<V> {
+    pub fn validate(&self, schema: &JsonSchema) -> Vec<ValidationError> {
+        schema.validate(self)
+    }
+}
+
+fn error(pointer: &str, keyword: &str, message: String) -> ValidationError {
+    ValidationError { pointer: pointer.to_string(), keyword: keyword.to_string(), message }
+}
+
+fn collect_patterns(node: &Instance, patterns: &mut Vec<String>) {
+    match node {
+        Instance::Object(members) => {
+            for (key, value) in members.iter() {
+                if let (true, Instance::String(pattern)) = (key == "pattern", value) {
+                    patterns.push(unescape(pattern).into_owned());
+                }
+                collect_patterns(value, patterns);
+            }
+        }
+        Instance::Array(items) => items.iter().for_each(|item| collect_patterns(item, patterns)),
+        _ => {}
+    }
+}
+
+fn parse_instance(json: &str, dialect: Dialect) -> Result<Instance<'_>, String> {
+    let result = JSONParser::parse(json, ParseOptions::default().max_depth(u8::MAX).dialect(dialect))?;
+    Ok(build_root(&result.json, dialect, &|e| e.value))
+}
+
+// Json5 numbers (e.g 0x10) are normalised first
+fn number(n: &str) -> Option<f64> {
+    json5_number_to_json(n).map_or_else(|| n.parse::<f64>().ok(), |n| n.parse::<f64>().ok())
+}
+
+/// Build document tree, entries are expected in document order as produced by the parser.
+fn build_root<'a, 'e, V: Debug + Clone + AsRef<str> + GetBytes>(entries: &'e [FlatJsonValue<V>], dialect: Dialect, value: &impl Fn(&'e FlatJsonValue<V>) -> Option<&'a str>) -> Instance<'a> {
+    let mut i = 0;
+    if entries.first().is_some_and(|e| e.pointer.pointer.is_empty()) {
+        return build_node(entries, &mut i, dialect, value);
+    }
+    let mut members = vec![];
+    while i < entries.len() {
+        let key = entries[i].pointer.pointer[1..].to_string();
+        members.push((key, build_node(entries, &mut i, dialect, value)));
+    }
+    Instance::Object(members)
+}
+
+fn build_node<'a, 'e, V: Debug + Clone + AsRef<str> + GetBytes>(entries: &'e [FlatJsonValue<V>], i: &mut usize, dialect: Dialect, value: &impl Fn(&'e FlatJsonValue<V>) -> Option<&'a str>) -> Instance<'a> {
+    let entry = &entries[*i];
+    *i += 1;
+    let raw = value(entry);
+    let is_array = match entry.pointer.value_type {
+        ValueType::Object(false, _) => return raw.map_or(Instance::Null, |raw| Instance::Raw(raw, false, dialect)),
+        ValueType::Object(true, _) => false,
+        ValueType::Array(_) if raw.is_some() && !has_children(entries, *i - 1) => return Instance::Raw(raw.unwrap(), true, dialect),
+        ValueType::Array(_) => true,
+        ValueType::Number => return Instance::Number(raw.unwrap_or("0")),
+        ValueType::String => return Instance::String(raw.unwrap_or("")),
+        ValueType::Bool => return Instance::Bool(raw == Some("true")),
+        ValueType::Null | ValueType::None => return Instance::Null,
+    };
+
+    let prefix = concat_string!(entry.pointer.pointer, "/");
+    let mut members = vec![];
+    while *i < entries.len() && entries[*i].pointer.pointer.starts_with(prefix.as_str()) {
+        let key = entries[*i].pointer.pointer[prefix.len()..].to_string();
+        members.push((key, build_node(entries, i, dialect, value)));
+    }
+    if is_array {
+        Instance::Array(members.into_iter().map(|(_, member)| member).collect())
+    } else {
+        Instance::Object(members)
+    }
+}
+
+fn has_type(node: &Instance, expected: &str) -> bool {
+    match (node, expected) {
+        (Instance::Number(n), "integer") => number(n).is_some_and(|n| n.fract() == 0.0),
+        (node, expected) => type_name(node) == expected,
+    }
+}
+
+fn type_name(node: &Instance) -> &'static str {
+    match node {
+        Instance::Null => "null",
+        Instance::Bool(_) => "boolean",
+        Instance::Number(_) => "number",
+        Instance::String(_) => "string",
+        Instance::Array(_) | Instance::Raw(_, true, _) => "array",
+        Instance::Object(_) | Instance::Raw(_, false, _) => "object",
+    }
+}
+
+fn equals(a: &Instance, b: &Instance) -> bool {
+    match (a, b) {
+        (Instance::Raw(a, _, _), Instance::Raw(b, _, _)) if a == b => true,
+        // Raw values are parsed on demand, also when they are children of parsed containers because of max_depth
+        (Instance::Raw(raw, _, dialect), other) | (other, Instance::Raw(raw, _, dialect)) => parse_instance(raw, *dialect).is_ok_and(|parsed| equals(&parsed, other)),
+        (Instance::Null, Instance::Null) => true,
+        (Instance::Bool(a), Instance::Bool(b)) => a == b,
+        (Instance::Number(a), Instance::Number(b)) => a == b || number(a).is_some_and(|a| number(b) == Some(a)),
+        (Instance::String(a), Instance::String(b)) => a == b || unescape(a) == unescape(b),
+        (Instance::Array(a), Instance::Array(b)) => a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| equals(a, b)),
+        (Instance::Object(a), Instance::Object(b)) => a.len() == b.len()
+            && a.iter().all(|(key, a)| b.iter().any(|(k, b)| k == key && equals(a, b))),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dialect, JSONParser, ParseOptions};
+    use crate::validation::JsonSchema;
+
+    const SCHEMA: &str = r##"{
+      "type": "object",
+      "required": ["id", "user", "items"],
+      "properties": {
+        "id": {"type": "integer", "minimum": 1},
+        "status": {"enum": ["on", "off"]},
+        "version": {"const": 2},
+        "user": {"$ref": "#/$defs/user"},
+        "items": {"type": "array", "maxItems": 3, "items": {"type": "object", "properties": {"qty": {"type": "number", "exclusiveMaximum": 10}}}},
+        "tags": {"type": "array", "prefixItems": [{"type": "string"}], "items": {"type": "integer"}},
+        "value": {"oneOf": [{"type": "string", "maxLength": 3}, {"type": "number"}]},
+        "any": {"anyOf": [{"type": "null"}, {"type": "boolean"}]},
+        "not_string": {"not": {"type": "string"}}
+      },
+      "additionalProperties": false,
+      "$defs": {
+        "user": {"type": "object", "required": ["name"], "properties": {"name": {"type": "string", "minLength": 2}, "address": {"$ref": "#/$defs/address"}}},
+        "address": {"type": "object", "properties": {"city": {"type": "string"}}, "additionalProperties": false}
+      }
+    }"##;
+
+    fn errors(json: &str, options: ParseOptions) -> Vec<(String, String)> {
+        let schema = JsonSchema::parse(SCHEMA).unwrap();
+        let result = JSONParser::parse(json, options).unwrap();
+        result.validate(&schema).into_iter().map(|e| (e.pointer, e.keyword)).collect()
+    }
+
+    #[test]
+    fn validate_valid_document() {
+        let json = r#"{"id": 1, "status": "on", "version": 2.0, "user": {"name": "bob", "address": {"city": "x"}}, "items": [{"qty": 1}, {"qty": 9.5}],
+                       "tags": ["a", 1, 2], "value": "abc", "any": null, "not_string": 1}"#;
+        assert_eq!(errors(json, ParseOptions::default()), vec![]);
+        assert_eq!(errors(json, ParseOptions::default().max_depth(1).parse_array(false)), vec![]);
+    }
+
+    #[test]
+    fn validate_reports_pointers() {
+        let json = r#"{"id": 0.5, "status": "maybe", "version": 3, "user": {"address": {"city": 1, "zip": "1"}}, "items": [{"qty": 10}, {}, {}, {}],
+                       "tags": [1, "a"], "value": "abcd", "any": 1, "not_string": "s", "unknown": true}"#;
+        let expected = vec![
+            ("/id", "type"), ("/id", "minimum"), ("/status", "enum"), ("/version", "const"),
+            ("/user", "required"), ("/user/address/city", "type"), ("/user/address/zip", "additionalProperties"),
+            ("/items", "maxItems"), ("/items/0/qty", "exclusiveMaximum"),
+            ("/tags/0", "type"), ("/tags/1", "type"), ("/value", "oneOf"), ("/any", "anyOf"), ("/not_string", "not"),
+            ("/unknown", "additionalProperties"),
+        ].into_iter().map(|(p, k)| (p.to_string(), k.to_string())).collect::<Vec<(String, String)>>();
+        assert_eq!(errors(json, ParseOptions::default()), expected);
+        // Subtrees which have not been parsed are parsed to be validated
+        assert_eq!(errors(json, ParseOptions::default().max_depth(1).parse_array(false)), expected);
+    }
+
+    #[test]
+    fn validate_const_at_every_max_depth() {
+        let schema = JsonSchema::parse(r#"{"properties": {"a": {"const": {"b": {"c": 1}, "l": [[1], {"x": null}]}}, "e": {"enum": [0, [{"f": true}]]}}}"#).unwrap();
+        let valid = r#"{"a": {"b": {"c": 1.0}, "l": [[1], {"x": null}]}, "e": [{"f": true}]}"#;
+        let invalid = r#"{"a": {"b": {"c": 2}, "l": [[1], {"x": null}]}, "e": [{"f": false}]}"#;
+        for max_depth in 1..=5 {
+            for parse_array in [true, false] {
+                let options = ParseOptions::default().max_depth(max_depth).parse_array(parse_array);
+                assert!(JSONParser::parse(valid, options.clone()).unwrap().validate(&schema).is_empty(), "max depth {} parse array {}", max_depth, parse_array);
+                let errors = JSONParser::parse(invalid, options).unwrap().validate(&schema).into_iter().map(|e| e.pointer).collect::<Vec<String>>();
+                assert_eq!(errors, vec!["/a", "/e"], "max depth {} parse array {}", max_depth, parse_array);
+            }
+        }
+    }
+
+    #[test]
+    fn validate_root_array_and_invalid_schema() {
+        let schema = JsonSchema::parse(r#"{"type": "array", "items": {"type": "object", "required": ["a"]}}"#).unwrap();
+        let result = JSONParser::parse_jsonl(b"{\"a\": 1}\n{\"b\": 1}\n", ParseOptions::default()).unwrap();
+        let errors = schema.validate(&result);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].pointer, "/1");
+        assert_eq!(errors[0].message, "Missing required property a");
+
+        let schema = JsonSchema::parse(r##"{"$ref": "#/$defs/missing"}"##).unwrap();
+        assert_eq!(schema.validate(&result)[0].keyword, "$ref");
+        let schema = JsonSchema::parse(r##"{"$ref": "#"}"##).unwrap();
+        assert_eq!(schema.validate(&result)[0].message, "Too many nested $ref resolving #");
+    }
+
+    #[test]
+    fn validate_json5_document() {
+        let schema = JsonSchema::parse(r#"{"properties": {"n": {"type": "integer", "minimum": 1, "const": 16}, "o": {"required": ["b"], "properties": {"b": {"const": [1]}}}}}"#).unwrap();
+        let json = "{n: 0x10, o: {a: 'x', /* comment */ b: [1,],},}";
+        for max_depth in [1, 2, 10] {
+            let result = JSONParser::parse(json, ParseOptions::default().dialect(Dialect::Json5).max_depth(max_depth)).unwrap();
+            assert_eq!(result.validate(&schema), vec![], "max depth {}", max_depth);
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn validate_pattern() {
+        let schema = JsonSchema::parse(r#"{"properties": {"code": {"type": "string", "pattern": "^\\d{3}$"}}}"#).unwrap();
+        let valid = JSONParser::parse(r#"{"code": "123"}"#, ParseOptions::default()).unwrap();
+        let invalid = JSONParser::parse(r#"{"code": "12a"}"#, ParseOptions::default()).unwrap();
+        assert!(valid.validate(&schema).is_empty());
+        assert_eq!(invalid.validate(&schema)[0].pointer, "/code");
+        assert!(JsonSchema::parse(r#"{"pattern": "("}"#).is_err());
+    }
+}